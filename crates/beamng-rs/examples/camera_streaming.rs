@@ -1,5 +1,6 @@
-use beamng_rs::sensors::{Camera, CameraConfig};
-use beamng_rs::vehicle::{Vehicle, VehicleOptions};
+use beamng_rs::api::vehicle::AiMode;
+use beamng_rs::sensors::{Camera, CameraConfig, CameraRawReadings};
+use beamng_rs::vehicle::VehicleOptions;
 use beamng_rs::{BeamNg, Scenario};
 
 #[tokio::main]
@@ -35,16 +36,14 @@ async fn main() -> beamng_proto::Result<()> {
     println!("Scenario created.");
 
     // Configure and load (connects vehicles during load, matching Python SDK)
-    let mut ego = Vehicle::new("ego", "etk800");
     bng.settings().set_deterministic(Some(60), None).await?;
-    bng.scenario()
-        .load_scenario(&scenario, true, &mut [&mut ego])
-        .await?;
+    let mut loaded = bng.scenario().load_scenario(&scenario, true).await?;
+    let mut ego = loaded.take("ego").expect("ego is in the scenario");
     bng.scenario().start(false).await?;
     println!("Scenario started.");
 
     bng.control().pause().await?;
-    ego.ai().set_mode("traffic").await?;
+    ego.ai().set_mode(AiMode::Traffic).await?;
 
     // Open the camera sensor with shared-memory streaming
     let camera = Camera::open(
@@ -69,11 +68,13 @@ async fn main() -> beamng_proto::Result<()> {
     .await?;
     println!("Camera opened.");
 
+    // Reused across frames so shared memory is copied without reallocating
+    let mut raw = CameraRawReadings::default();
     for i in 0..41 {
         bng.control().step(10, true).await?;
 
         // Read raw bytes directly from shared memory — the fastest path
-        let raw = camera.stream_raw()?;
+        camera.stream_raw_into(&mut raw)?;
 
         if i % 10 == 0 {
             if let Some(ref colour) = raw.colour {
@@ -1,9 +1,10 @@
 use std::time::Instant;
 
+use beamng_rs::api::vehicle::VehicleInputs;
 use beamng_rs::sensors::{
     AdvancedImu, AdvancedImuConfig, Camera, CameraConfig, Gps, GpsConfig, GpsReading, ImuReading,
 };
-use beamng_rs::vehicle::{Vehicle, VehicleOptions};
+use beamng_rs::vehicle::VehicleOptions;
 use beamng_rs::{BeamNg, Scenario};
 use eframe::egui;
 use tokio::sync::mpsc;
@@ -228,15 +229,12 @@ fn main() -> eframe::Result {
                 scenario.make(&mut bng).await.unwrap();
                 println!("Scenario created.");
 
-                let mut ego = Vehicle::new("ego", "etk800");
                 bng.settings()
                     .set_deterministic(Some(60), None)
                     .await
                     .unwrap();
-                bng.scenario()
-                    .load_scenario(&scenario, true, &mut [&mut ego])
-                    .await
-                    .unwrap();
+                let mut loaded = bng.scenario().load_scenario(&scenario, true).await.unwrap();
+                let mut ego = loaded.take("ego").expect("ego is in the scenario");
                 bng.scenario().start(false).await.unwrap();
                 bng.control().pause().await.unwrap();
                 println!("Scenario started (deterministic, paused).");
@@ -304,12 +302,11 @@ fn main() -> eframe::Result {
                         let _ = ego
                             .root()
                             .control(
-                                Some(steering),
-                                Some(throttle),
-                                Some(brake),
-                                Some(parkingbrake),
-                                None,
-                                None,
+                                &VehicleInputs::new()
+                                    .steering(steering)
+                                    .throttle(throttle)
+                                    .brake(brake)
+                                    .parkingbrake(parkingbrake),
                             )
                             .await;
                     }
@@ -2,6 +2,7 @@ use beamng_proto::types::{StrDict, Vec3};
 use beamng_proto::Result;
 
 use crate::beamng::BeamNg;
+use crate::sensors::AnnotationClasses;
 
 fn vec3_val(v: Vec3) -> rmpv::Value {
     rmpv::Value::Array(vec![
@@ -79,4 +80,10 @@ impl CameraApi<'_> {
     pub async fn get_annotations(&mut self) -> Result<StrDict> {
         self.bng.conn()?.request("GetAnnotations", &[]).await
     }
+
+    /// Get the annotation colour table as typed [`AnnotationClasses`].
+    pub async fn get_annotation_classes(&mut self) -> Result<AnnotationClasses> {
+        let resp = self.get_annotations().await?;
+        Ok(AnnotationClasses::from_response(&resp))
+    }
 }
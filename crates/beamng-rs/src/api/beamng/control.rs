@@ -1,7 +1,9 @@
 use beamng_proto::types::StrDict;
 use beamng_proto::Result;
+use serde::de::DeserializeOwned;
 
 use crate::beamng::BeamNg;
+use crate::lua::decode_result;
 
 /// API for controlling the flow of the simulation — pausing, resuming, stepping,
 /// and executing custom Lua code.
@@ -24,6 +26,7 @@ impl ControlApi<'_> {
     ///
     /// If `wait` is true, blocks until the simulator has finished simulating the steps.
     pub async fn step(&mut self, count: u32, wait: bool) -> Result<()> {
+        self.bng.flush_pending_closes().await?;
         let conn = self.bng.conn()?;
         let fields = &[
             ("count", rmpv::Value::from(count)),
@@ -67,6 +70,13 @@ impl ControlApi<'_> {
         Ok(resp.get("resp").cloned())
     }
 
+    /// Execute a Lua chunk in the game engine VM and decode the value it returns.
+    ///
+    /// A chunk returning nothing decodes as `()` or `None`.
+    pub async fn queue_lua_command_as<T: DeserializeOwned>(&mut self, chunk: &str) -> Result<T> {
+        decode_result(self.queue_lua_command(chunk, true).await?)
+    }
+
     /// Return to the main menu, closing any loaded scenario.
     pub async fn return_to_main_menu(&mut self) -> Result<()> {
         self.bng
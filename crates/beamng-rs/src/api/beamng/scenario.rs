@@ -2,8 +2,7 @@ use beamng_proto::types::StrDict;
 use beamng_proto::{BngError, Result};
 
 use crate::beamng::BeamNg;
-use crate::scenario::Scenario;
-use crate::vehicle::Vehicle;
+use crate::scenario::{LoadedScenario, Scenario};
 
 /// API for working with scenarios, levels and scenario objects.
 pub struct ScenarioApi<'a> {
@@ -37,31 +36,51 @@ impl ScenarioApi<'_> {
             .ok_or_else(|| BngError::ValueError("Missing scenario name".into()))
     }
 
-    /// Load a [`Scenario`] that was previously created with [`Scenario::make`],
-    /// and optionally connect its vehicles (matching Python SDK behavior).
+    /// Load a [`Scenario`] that was previously created with [`Scenario::make`] and
+    /// connect its vehicles (matching Python SDK behavior).
     ///
-    /// After the map is loaded, this discovers spawned vehicles, identifies
-    /// the player vehicle, and establishes per-vehicle TCP connections —
-    /// exactly like Python's `scenario.load()`.
+    /// After the map is loaded, this checks that every vehicle of the scenario was
+    /// spawned, identifies the player vehicle, and establishes per-vehicle TCP
+    /// connections — exactly like Python's `scenario.load()`. The handles keep the
+    /// models and options of the scenario's descriptors. If any vehicle fails to
+    /// connect, the ones already connected are disconnected and the error returned.
     pub async fn load_scenario(
         &mut self,
         scenario: &Scenario,
         precompile_shaders: bool,
-        vehicles: &mut [&mut Vehicle],
-    ) -> Result<()> {
+    ) -> Result<LoadedScenario> {
         let path = scenario.path().ok_or_else(|| {
             BngError::ValueError("Scenario has no path; call make() first".into())
         })?;
         self.load(path, precompile_shaders).await?;
 
         // Post-load vehicle discovery and connection (matches Python SDK)
-        self.bng.vehicles().get_current_info(false).await?;
-        self.bng.vehicles().get_player_vehicle_id().await?;
-        for veh in vehicles.iter_mut() {
-            self.bng.vehicles().connect_vehicle(veh).await?;
+        let current = self.bng.vehicles().get_current_info(false).await?;
+        let mut vehicles = scenario.spawned_vehicles(current.as_ref())?;
+        let player = self.bng.vehicles().get_player_vehicle_id().await?;
+        let player_vid = player
+            .get("vid")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        let mut connected = Ok(());
+        for veh in vehicles.values_mut() {
+            connected = self.bng.vehicles().connect_vehicle(veh).await;
+            if connected.is_err() {
+                break;
+            }
+        }
+        if let Err(e) = connected {
+            // Close the connections already made rather than leaving them behind.
+            for veh in vehicles.values_mut() {
+                veh.disconnect();
+            }
+            return Err(e);
         }
 
-        Ok(())
+        Ok(LoadedScenario {
+            vehicles,
+            player_vid,
+        })
     }
 
     /// Load a scenario by its path.
@@ -103,8 +103,9 @@ impl VehiclesApi<'_> {
         Ok(())
     }
 
-    /// Despawn a vehicle from the simulation.
+    /// Despawn a vehicle from the simulation, closing the sensors attached to it first.
     pub async fn despawn(&mut self, vehicle: &mut Vehicle) -> Result<()> {
+        self.bng.close_vehicle_sensors(&vehicle.vid).await?;
         vehicle.disconnect();
         self.bng
             .conn()?
@@ -1,29 +1,375 @@
-use beamng_proto::Result;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
 
+use beamng_proto::types::{value_as_u64, Vec3};
+use beamng_proto::{BngError, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::lua::{self, Chunk};
 use crate::vehicle::Vehicle;
 
+/// A point of an AI script: a position the vehicle must be at `t` seconds after the
+/// script starts.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScriptPoint {
+    /// World position in metres.
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// Seconds after the start of the script.
+    pub t: f64,
+}
+
+impl ScriptPoint {
+    pub fn new(pos: Vec3, t: f64) -> Self {
+        Self {
+            x: pos.0,
+            y: pos.1,
+            z: pos.2,
+            t,
+        }
+    }
+}
+
+/// A point of an AI line: a position to drive through and the speed to have there.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LinePoint {
+    pub pos: Vec3,
+    /// Target speed in m/s.
+    pub speed: f64,
+}
+
+/// Driving modes of the vehicle AI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AiMode {
+    /// The AI does not drive.
+    Disabled,
+    /// Drive to random destinations.
+    Random,
+    /// Drive across the whole road network.
+    Span,
+    /// Drive to the waypoint set with [`AIApi::set_waypoint`].
+    Manual,
+    /// Drive like traffic, obeying the road rules.
+    Traffic,
+    /// Chase the target vehicle.
+    Chase,
+    /// Flee from the target vehicle.
+    Flee,
+    /// Brake to a standstill.
+    Stopping,
+}
+
+impl AiMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AiMode::Disabled => "disabled",
+            AiMode::Random => "random",
+            AiMode::Span => "span",
+            AiMode::Manual => "manual",
+            AiMode::Traffic => "traffic",
+            AiMode::Chase => "chase",
+            AiMode::Flee => "flee",
+            AiMode::Stopping => "stopping",
+        }
+    }
+}
+
+impl FromStr for AiMode {
+    type Err = BngError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "disabled" => AiMode::Disabled,
+            "random" => AiMode::Random,
+            "span" => AiMode::Span,
+            "manual" => AiMode::Manual,
+            "traffic" => AiMode::Traffic,
+            "chase" => AiMode::Chase,
+            "flee" => AiMode::Flee,
+            "stopping" => AiMode::Stopping,
+            _ => return Err(BngError::ValueError(format!("Unknown AI mode \"{s}\""))),
+        })
+    }
+}
+
+/// How the AI treats its target speed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpeedMode {
+    /// Drive at most at the target speed, slower where the road requires it.
+    #[default]
+    Limit,
+    /// Hold the target speed regardless of the road.
+    Set,
+}
+
+impl SpeedMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SpeedMode::Limit => "limit",
+            SpeedMode::Set => "set",
+        }
+    }
+}
+
+/// AI settings applied together with [`AIApi::configure`]. Settings left as `None`
+/// are not changed.
+///
+/// ```
+/// use beamng_rs::api::vehicle::{AiConfig, AiMode, SpeedMode};
+///
+/// let config = AiConfig::new(AiMode::Traffic)
+///     .speed(13.9, SpeedMode::Limit)
+///     .aggression(0.3)
+///     .drive_in_lane(true);
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AiConfig {
+    /// The driving mode, applied after the other settings. Ignored when a route
+    /// is set, since the route already puts the AI in manual mode.
+    pub mode: Option<AiMode>,
+    /// Target speed in m/s and how it is applied.
+    pub speed: Option<(f64, SpeedMode)>,
+    /// Aggression from 0 to 1.
+    pub aggression: Option<f64>,
+    /// Whether to keep to the correct lane rather than use the whole road.
+    pub drive_in_lane: Option<bool>,
+    /// Whether to steer and brake to avoid other vehicles.
+    pub avoid_crashes: Option<bool>,
+    /// Waypoint names to drive through in order, which puts the AI in
+    /// [`AiMode::Manual`].
+    pub route: Option<Vec<String>>,
+}
+
+impl AiConfig {
+    /// Settings that only switch the AI to `mode`.
+    pub fn new(mode: AiMode) -> Self {
+        Self {
+            mode: Some(mode),
+            ..Default::default()
+        }
+    }
+
+    /// Set the target speed in m/s and how it is applied.
+    pub fn speed(mut self, speed: f64, mode: SpeedMode) -> Self {
+        self.speed = Some((speed, mode));
+        self
+    }
+
+    /// Set the aggression, from 0 to 1.
+    pub fn aggression(mut self, aggression: f64) -> Self {
+        self.aggression = Some(aggression);
+        self
+    }
+
+    /// Keep to the correct lane, or use the whole road.
+    pub fn drive_in_lane(mut self, lane: bool) -> Self {
+        self.drive_in_lane = Some(lane);
+        self
+    }
+
+    /// Avoid other vehicles, or ignore them.
+    pub fn avoid_crashes(mut self, avoid: bool) -> Self {
+        self.avoid_crashes = Some(avoid);
+        self
+    }
+
+    /// Drive through the named waypoints in order, in [`AiMode::Manual`].
+    pub fn route(mut self, waypoints: Vec<String>) -> Self {
+        self.route = Some(waypoints);
+        self
+    }
+
+    /// Check that the speed is finite and non-negative, the aggression is in `[0, 1]`
+    /// and any route is non-empty and driven in manual mode.
+    pub fn validate(&self) -> Result<()> {
+        if let Some((speed, _)) = self.speed {
+            if !(speed.is_finite() && speed >= 0.0) {
+                return Err(BngError::ValueError(format!(
+                    "AI speed must be non-negative, got {speed}"
+                )));
+            }
+        }
+        if let Some(a) = self.aggression {
+            if !(0.0..=1.0).contains(&a) {
+                return Err(BngError::ValueError(format!(
+                    "AI aggression must be in [0, 1], got {a}"
+                )));
+            }
+        }
+        if let Some(route) = &self.route {
+            if route.is_empty() {
+                return Err(BngError::ValueError("AI route must not be empty".into()));
+            }
+            if self.mode.is_some_and(|m| m != AiMode::Manual) {
+                return Err(BngError::ValueError(
+                    "An AI route can only be driven in manual mode".into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The vehicle Lua chunk applying the settings, with the mode last. A route is
+    /// sent first instead and no mode is sent with it: `ai.driveUsingPath` already
+    /// switches to manual mode, and a later `ai.setMode` would drop the route.
+    fn lua_chunk(&self) -> String {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        let mut chunk = Chunk::new();
+        if let Some(route) = &self.route {
+            let path = BTreeMap::from([("wpTargetList", route)]);
+            chunk = chunk.push(lua::call("ai.driveUsingPath").arg(path));
+        }
+        if let Some((speed, mode)) = self.speed {
+            chunk = chunk
+                .push(lua::call("ai.setSpeedMode").arg(mode.as_str()))
+                .push(lua::call("ai.setSpeed").arg(speed));
+        }
+        if let Some(a) = self.aggression {
+            chunk = chunk.push(lua::call("ai.setAggression").arg(a));
+        }
+        if let Some(lane) = self.drive_in_lane {
+            chunk = chunk.push(lua::call("ai.driveInLane").arg(on_off(lane)));
+        }
+        if let Some(avoid) = self.avoid_crashes {
+            chunk = chunk.push(lua::call("ai.setAvoidCars").arg(on_off(avoid)));
+        }
+        if let Some(mode) = self.mode.filter(|_| self.route.is_none()) {
+            chunk = chunk.push(lua::call("ai.setMode").arg(mode.as_str()));
+        }
+        chunk.to_string()
+    }
+}
+
+/// The AI state read back with [`AIApi::get_state`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AiState {
+    pub mode: Option<AiMode>,
+    /// The waypoint the AI is driving to, in manual mode.
+    pub target_waypoint: Option<String>,
+    /// The object ID of the vehicle the AI chases or flees.
+    pub target_vehicle: Option<u64>,
+}
+
+impl AiState {
+    fn from_value(value: &rmpv::Value) -> Self {
+        let get = |key: &str| {
+            value
+                .as_map()
+                .and_then(|m| m.iter().find(|(k, _)| k.as_str() == Some(key)))
+                .map(|(_, v)| v)
+        };
+        AiState {
+            mode: get("mode")
+                .and_then(|v| v.as_str())
+                .and_then(|m| m.parse().ok()),
+            target_waypoint: get("targetWaypoint")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            target_vehicle: get("targetObjectID").and_then(value_as_u64),
+        }
+    }
+}
+
+/// How the AI behaves towards the target of [`AIApi::set_target`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetMode {
+    Chase,
+    Flee,
+}
+
+impl From<TargetMode> for AiMode {
+    fn from(mode: TargetMode) -> Self {
+        match mode {
+            TargetMode::Chase => AiMode::Chase,
+            TargetMode::Flee => AiMode::Flee,
+        }
+    }
+}
+
+/// Read an AI script written by [`AIApi::stop_recording`], a JSON list of
+/// [`ScriptPoint`]s.
+///
+/// The path is read on this machine, so a recorded script can only be loaded
+/// directly when the simulator runs on the same machine; otherwise copy the file
+/// from the simulator's user folder first.
+pub fn load_script(path: impl AsRef<Path>) -> Result<Vec<ScriptPoint>> {
+    let text = std::fs::read_to_string(path.as_ref())?;
+    serde_json::from_str(&text).map_err(|e| {
+        BngError::ValueError(format!(
+            "Invalid AI script {}: {e}",
+            path.as_ref().display()
+        ))
+    })
+}
+
+/// Points sent to the AI, encoded as the tables its Lua handlers index by name.
+trait AiPoint {
+    fn to_value(&self) -> rmpv::Value;
+}
+
+fn table(pairs: Vec<(&str, rmpv::Value)>) -> rmpv::Value {
+    rmpv::Value::Map(
+        pairs
+            .into_iter()
+            .map(|(k, v)| (rmpv::Value::from(k), v))
+            .collect(),
+    )
+}
+
+impl AiPoint for ScriptPoint {
+    fn to_value(&self) -> rmpv::Value {
+        table(vec![
+            ("x", rmpv::Value::from(self.x)),
+            ("y", rmpv::Value::from(self.y)),
+            ("z", rmpv::Value::from(self.z)),
+            ("t", rmpv::Value::from(self.t)),
+        ])
+    }
+}
+
+impl AiPoint for LinePoint {
+    fn to_value(&self) -> rmpv::Value {
+        let (x, y, z) = self.pos;
+        table(vec![
+            (
+                "pos",
+                rmpv::Value::Array(vec![x.into(), y.into(), z.into()]),
+            ),
+            ("speed", rmpv::Value::from(self.speed)),
+        ])
+    }
+}
+
+/// Encode a list of points as a msgpack array of tables.
+fn points_value<T: AiPoint>(points: &[T]) -> rmpv::Value {
+    rmpv::Value::Array(points.iter().map(AiPoint::to_value).collect())
+}
+
 /// API for controlling vehicle AI behavior.
 pub struct AIApi<'a> {
     pub(crate) vehicle: &'a mut Vehicle,
 }
 
 impl AIApi<'_> {
-    /// Set the AI mode (e.g. "disabled", "span", "manual", "traffic", "flee", "chase", "random").
-    pub async fn set_mode(&mut self, mode: &str) -> Result<()> {
+    /// Set the AI mode.
+    pub async fn set_mode(&mut self, mode: AiMode) -> Result<()> {
         self.vehicle
-            .send_vehicle_request("SetAiMode", &[("mode", rmpv::Value::from(mode))])
+            .send_vehicle_request("SetAiMode", &[("mode", rmpv::Value::from(mode.as_str()))])
             .await?;
         Ok(())
     }
 
     /// Set the AI target speed in m/s.
-    pub async fn set_speed(&mut self, speed: f64, mode: &str) -> Result<()> {
+    pub async fn set_speed(&mut self, speed: f64, mode: SpeedMode) -> Result<()> {
         self.vehicle
             .send_vehicle_request(
                 "SetAiSpeed",
                 &[
                     ("speed", rmpv::Value::from(speed)),
-                    ("mode", rmpv::Value::from(mode)),
+                    ("mode", rmpv::Value::from(mode.as_str())),
                 ],
             )
             .await?;
@@ -59,4 +405,155 @@ impl AIApi<'_> {
             .await?;
         Ok(())
     }
+
+    /// Make the AI follow a script exactly, reaching each point at its time.
+    ///
+    /// With `cling` the points are snapped to the road surface.
+    pub async fn set_script(&mut self, script: &[ScriptPoint], cling: bool) -> Result<()> {
+        self.vehicle
+            .send_vehicle_request(
+                "SetAiScript",
+                &[
+                    ("script", points_value(script)),
+                    ("cling", rmpv::Value::from(cling)),
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Make the AI drive along a line of positions at the given speeds.
+    ///
+    /// With `cling` the points are snapped to the road surface.
+    pub async fn set_line(&mut self, line: &[LinePoint], cling: bool) -> Result<()> {
+        self.vehicle
+            .send_vehicle_request(
+                "SetAiLine",
+                &[
+                    ("line", points_value(line)),
+                    ("cling", rmpv::Value::from(cling)),
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Make the AI chase or flee the vehicle with ID `target`.
+    pub async fn set_target(&mut self, target: &str, mode: TargetMode) -> Result<()> {
+        self.set_mode(mode.into()).await?;
+        self.vehicle
+            .send_vehicle_request("SetAiTarget", &[("target", rmpv::Value::from(target))])
+            .await?;
+        Ok(())
+    }
+
+    /// Start recording the vehicle's trajectory as an AI script.
+    pub async fn start_recording(&mut self) -> Result<()> {
+        self.vehicle
+            .send_vehicle_request("StartRecording", &[])
+            .await?;
+        Ok(())
+    }
+
+    /// Stop recording and write the script to `filename` on the simulator's side,
+    /// relative to its user folder. Read it back with [`load_script`], which only sees
+    /// the file if the simulator runs on the same machine, and replay it with
+    /// [`set_script`](Self::set_script).
+    pub async fn stop_recording(&mut self, filename: &str) -> Result<()> {
+        self.vehicle
+            .send_vehicle_request(
+                "StopRecording",
+                &[("filename", rmpv::Value::from(filename))],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Validate and apply several AI settings at once, in a single Lua chunk run
+    /// within one simulator frame.
+    pub async fn configure(&mut self, config: &AiConfig) -> Result<()> {
+        config.validate()?;
+        let chunk = config.lua_chunk();
+        if chunk.is_empty() {
+            return Ok(());
+        }
+        self.vehicle.queue_lua_command(&chunk, false).await?;
+        Ok(())
+    }
+
+    /// Read back the AI's current mode and target.
+    pub async fn get_state(&mut self) -> Result<AiState> {
+        let chunk = "return {mode = ai.mode, targetWaypoint = ai.manualTargetName, \
+                     targetObjectID = ai.targetObjectID}";
+        let result = self.vehicle.queue_lua_command(chunk, true).await?;
+        Ok(result.map(|v| AiState::from_value(&v)).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ai_config_chunk_and_state() {
+        let config = AiConfig::new(AiMode::Span)
+            .speed(20.0, SpeedMode::Set)
+            .avoid_crashes(false);
+        assert_eq!(
+            config.lua_chunk(),
+            "ai.setSpeedMode(\"set\")\nai.setSpeed(20)\nai.setAvoidCars(\"off\")\nai.setMode(\"span\")"
+        );
+        let routed = AiConfig::default().route(vec!["wp_it's".into()]);
+        assert_eq!(
+            routed.lua_chunk(),
+            r#"ai.driveUsingPath({wpTargetList = {"wp_it's"}})"#
+        );
+        assert!(AiConfig::new(AiMode::Traffic)
+            .route(vec!["wp1".into()])
+            .validate()
+            .is_err());
+        assert!(AiConfig::default().aggression(1.5).validate().is_err());
+
+        let value = rmpv::Value::Map(vec![
+            (rmpv::Value::from("mode"), rmpv::Value::from("chase")),
+            (rmpv::Value::from("targetObjectID"), rmpv::Value::from(1234)),
+        ]);
+        let state = AiState::from_value(&value);
+        assert_eq!(state.mode, Some(AiMode::Chase));
+        assert_eq!(state.target_vehicle, Some(1234));
+        assert_eq!("nonsense".parse::<AiMode>().ok(), None);
+    }
+
+    #[test]
+    fn test_points_encoding_and_script_file() {
+        let script = [ScriptPoint::new((1.0, 2.0, 3.0), 0.5)];
+        assert_eq!(
+            points_value(&script),
+            rmpv::Value::Array(vec![table(vec![
+                ("x", rmpv::Value::from(1.0)),
+                ("y", rmpv::Value::from(2.0)),
+                ("z", rmpv::Value::from(3.0)),
+                ("t", rmpv::Value::from(0.5)),
+            ])])
+        );
+        let line = [LinePoint {
+            pos: (1.0, 2.0, 3.0),
+            speed: 10.0,
+        }];
+        let pos = rmpv::Value::Array(vec![1.0.into(), 2.0.into(), 3.0.into()]);
+        assert_eq!(
+            points_value(&line),
+            rmpv::Value::Array(vec![table(vec![
+                ("pos", pos),
+                ("speed", rmpv::Value::from(10.0)),
+            ])])
+        );
+
+        let path = std::env::temp_dir().join(format!("ai_script_{}.json", std::process::id()));
+        std::fs::write(&path, r#"[{"x": 1, "y": 2, "z": 3, "t": 0.5}]"#).unwrap();
+        assert_eq!(load_script(&path).unwrap(), script.to_vec());
+        std::fs::write(&path, r#"[{"x": 1}]"#).unwrap();
+        assert!(matches!(load_script(&path), Err(BngError::ValueError(_))));
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,262 @@
+use beamng_proto::types::Color;
+use beamng_proto::{BngError, Result};
+
+use super::{PartConfig, PartOptions};
+use crate::beamng::BeamNg;
+use crate::lua;
+use crate::vehicle::Vehicle;
+
+/// Headlight states for [`Lights::headlights`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Headlights {
+    Off,
+    LowBeam,
+    HighBeam,
+}
+
+/// Light bar states for [`Lights::lightbar`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightBar {
+    Off,
+    Lights,
+    LightsAndSiren,
+}
+
+/// Light settings for [`ControlsApi::set_lights`]. Lights left as `None` keep their
+/// current state.
+///
+/// ```
+/// use beamng_rs::api::vehicle::{Headlights, Lights};
+///
+/// let lights = Lights::new().headlights(Headlights::LowBeam).hazard_signal(true);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Lights {
+    pub left_signal: Option<bool>,
+    pub right_signal: Option<bool>,
+    pub hazard_signal: Option<bool>,
+    pub headlights: Option<Headlights>,
+    pub fog_lights: Option<bool>,
+    pub lightbar: Option<LightBar>,
+}
+
+impl Lights {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn left_signal(mut self, on: bool) -> Self {
+        self.left_signal = Some(on);
+        self
+    }
+
+    pub fn right_signal(mut self, on: bool) -> Self {
+        self.right_signal = Some(on);
+        self
+    }
+
+    pub fn hazard_signal(mut self, on: bool) -> Self {
+        self.hazard_signal = Some(on);
+        self
+    }
+
+    pub fn headlights(mut self, state: Headlights) -> Self {
+        self.headlights = Some(state);
+        self
+    }
+
+    pub fn fog_lights(mut self, on: bool) -> Self {
+        self.fog_lights = Some(on);
+        self
+    }
+
+    pub fn lightbar(mut self, state: LightBar) -> Self {
+        self.lightbar = Some(state);
+        self
+    }
+
+    /// The fields of a `SetLights` request.
+    fn fields(&self) -> Vec<(&'static str, rmpv::Value)> {
+        let mut fields = Vec::new();
+        let signals = [
+            ("leftSignal", self.left_signal),
+            ("rightSignal", self.right_signal),
+            ("hazardSignal", self.hazard_signal),
+        ];
+        for (name, on) in signals {
+            if let Some(on) = on {
+                fields.push((name, rmpv::Value::from(on)));
+            }
+        }
+        if let Some(state) = self.headlights {
+            fields.push(("headLights", rmpv::Value::from(state as u8)));
+        }
+        if let Some(on) = self.fog_lights {
+            fields.push(("fogLights", rmpv::Value::from(on as u8)));
+        }
+        if let Some(state) = self.lightbar {
+            fields.push(("lightBar", rmpv::Value::from(state as u8)));
+        }
+        fields
+    }
+}
+
+/// Gearbox shift modes for [`ControlsApi::set_shift_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShiftMode {
+    /// Manual shifting with a manual clutch.
+    RealisticManual,
+    /// Manual shifting with an automatic clutch.
+    RealisticManualAutoClutch,
+    /// Automatic shifting where braking at a standstill engages reverse.
+    Arcade,
+    /// Automatic shifting with a PRND selector.
+    RealisticAutomatic,
+}
+
+impl ShiftMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ShiftMode::RealisticManual => "realistic_manual",
+            ShiftMode::RealisticManualAutoClutch => "realistic_manual_auto_clutch",
+            ShiftMode::Arcade => "arcade",
+            ShiftMode::RealisticAutomatic => "realistic_automatic",
+        }
+    }
+}
+
+/// API for a vehicle's lights, gearbox mode, horn, paint, recovery and parts.
+pub struct ControlsApi<'a> {
+    pub(crate) vehicle: &'a mut Vehicle,
+}
+
+impl ControlsApi<'_> {
+    /// Run a Lua chunk in the vehicle's VM.
+    async fn lua(&mut self, chunk: &str) -> Result<()> {
+        self.vehicle.queue_lua_command(chunk, false).await?;
+        Ok(())
+    }
+
+    /// Switch the signals, headlights, fog lights and light bar.
+    pub async fn set_lights(&mut self, lights: &Lights) -> Result<()> {
+        self.vehicle
+            .send_vehicle_request("SetLights", &lights.fields())
+            .await?;
+        Ok(())
+    }
+
+    /// Set how the gearbox shifts.
+    pub async fn set_shift_mode(&mut self, mode: ShiftMode) -> Result<()> {
+        self.vehicle
+            .send_vehicle_request(
+                "SetShiftMode",
+                &[("mode", rmpv::Value::from(mode.as_str()))],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Press or release the horn.
+    pub async fn set_horn(&mut self, on: bool) -> Result<()> {
+        self.lua(&lua::call("electrics.horn").arg(on).to_string())
+            .await
+    }
+
+    /// Set the primary paint colour as RGBA, each channel in `[0, 1]`.
+    pub async fn set_color(&mut self, rgba: Color) -> Result<()> {
+        self.vehicle
+            .send_vehicle_request(
+                "SetColor",
+                &[
+                    ("r", rmpv::Value::from(rgba.0)),
+                    ("g", rmpv::Value::from(rgba.1)),
+                    ("b", rmpv::Value::from(rgba.2)),
+                    ("a", rmpv::Value::from(rgba.3)),
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Reset the vehicle to its spawn state in place, repairing all damage.
+    pub async fn reset(&mut self) -> Result<()> {
+        self.lua("obj:requestReset(RESET_PHYSICS)").await
+    }
+
+    /// Recover the vehicle in place: put it back on its wheels where it stands.
+    pub async fn recover(&mut self) -> Result<()> {
+        self.lua("recovery.recoverInPlace()").await
+    }
+
+    /// Get the slots of the vehicle and the parts available for each.
+    pub async fn get_part_options(&mut self) -> Result<PartOptions> {
+        let resp = self
+            .vehicle
+            .send_vehicle_request("GetPartOptions", &[])
+            .await?;
+        let options = resp
+            .get("options")
+            .ok_or_else(|| BngError::ValueError("GetPartOptions response has no options".into()))?;
+        Ok(PartOptions::from_value(options))
+    }
+
+    /// Get the vehicle's current parts and tuning variables.
+    pub async fn get_part_config(&mut self) -> Result<PartConfig> {
+        let mut resp = self
+            .vehicle
+            .send_vehicle_request("GetPartConfig", &[])
+            .await?;
+        let config = resp
+            .remove("config")
+            .ok_or_else(|| BngError::ValueError("GetPartConfig response has no config".into()))?;
+        PartConfig::from_value(config)
+    }
+
+    /// Apply a part configuration, such as one from
+    /// [`get_part_config`](Self::get_part_config) with some parts or variables changed.
+    ///
+    /// The simulator respawns the vehicle to apply the parts, so this waits for the
+    /// respawn and reconnects the vehicle.
+    pub async fn set_part_config(&mut self, bng: &mut BeamNg, config: &PartConfig) -> Result<()> {
+        let config = config.to_value()?;
+        // The vehicle's Lua VM reloads with the new parts, so no response comes back.
+        self.vehicle
+            .connection
+            .as_mut()
+            .ok_or_else(|| BngError::Disconnected("Vehicle not connected".into()))?
+            .send_raw("SetPartConfig", &[("config", config)])
+            .await?;
+        bng.vehicles().await_spawn(&self.vehicle.vid).await?;
+        self.vehicle.disconnect();
+        bng.vehicles().connect_vehicle(self.vehicle).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lights_fields_and_shift_modes() {
+        assert!(Lights::new().fields().is_empty());
+        let fields = Lights::new()
+            .right_signal(false)
+            .headlights(Headlights::HighBeam)
+            .fog_lights(true)
+            .lightbar(LightBar::LightsAndSiren)
+            .fields();
+        assert_eq!(
+            fields,
+            vec![
+                ("rightSignal", rmpv::Value::from(false)),
+                ("headLights", rmpv::Value::from(2)),
+                ("fogLights", rmpv::Value::from(1)),
+                ("lightBar", rmpv::Value::from(2)),
+            ]
+        );
+        assert_eq!(
+            ShiftMode::RealisticManualAutoClutch.as_str(),
+            "realistic_manual_auto_clutch"
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use std::ops::RangeInclusive;
+
+use beamng_proto::{BngError, Result};
+
+use crate::vehicle::Vehicle;
+
+/// Gears accepted by [`VehicleInputs::validate`]: reverse (-1), neutral (0) and up to
+/// 16 forward gears, which covers every stock BeamNG gearbox.
+pub const GEAR_RANGE: RangeInclusive<i32> = -1..=16;
+
+/// Driver inputs for [`RootApi::control`](super::RootApi::control).
+///
+/// Inputs left as `None` keep their current value in the simulator.
+///
+/// ```
+/// use beamng_rs::api::vehicle::VehicleInputs;
+///
+/// let inputs = VehicleInputs::new().steering(-0.2).throttle(0.5);
+/// assert!(inputs.validate().is_ok());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VehicleInputs {
+    /// Steering from -1 (full left) to 1 (full right).
+    pub steering: Option<f64>,
+    pub throttle: Option<f64>,
+    pub brake: Option<f64>,
+    pub parkingbrake: Option<f64>,
+    pub clutch: Option<f64>,
+    /// Gear, with -1 for reverse and 0 for neutral.
+    pub gear: Option<i32>,
+}
+
+impl VehicleInputs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn steering(mut self, steering: f64) -> Self {
+        self.steering = Some(steering);
+        self
+    }
+
+    pub fn throttle(mut self, throttle: f64) -> Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    pub fn brake(mut self, brake: f64) -> Self {
+        self.brake = Some(brake);
+        self
+    }
+
+    pub fn parkingbrake(mut self, parkingbrake: f64) -> Self {
+        self.parkingbrake = Some(parkingbrake);
+        self
+    }
+
+    pub fn clutch(mut self, clutch: f64) -> Self {
+        self.clutch = Some(clutch);
+        self
+    }
+
+    pub fn gear(mut self, gear: i32) -> Self {
+        self.gear = Some(gear);
+        self
+    }
+
+    /// Whether no input is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Overwrite the inputs set in `other`, keeping the rest.
+    pub fn merge(&mut self, other: &VehicleInputs) {
+        self.steering = other.steering.or(self.steering);
+        self.throttle = other.throttle.or(self.throttle);
+        self.brake = other.brake.or(self.brake);
+        self.parkingbrake = other.parkingbrake.or(self.parkingbrake);
+        self.clutch = other.clutch.or(self.clutch);
+        self.gear = other.gear.or(self.gear);
+    }
+
+    /// Check that steering is in `[-1, 1]`, the pedals and parking brake in `[0, 1]`
+    /// and the gear in [`GEAR_RANGE`].
+    pub fn validate(&self) -> Result<()> {
+        let check = |name: &str, value: Option<f64>, range: RangeInclusive<f64>| match value {
+            Some(v) if !range.contains(&v) => Err(BngError::ValueError(format!(
+                "{name} must be in [{}, {}], got {v}",
+                range.start(),
+                range.end()
+            ))),
+            _ => Ok(()),
+        };
+        check("steering", self.steering, -1.0..=1.0)?;
+        check("throttle", self.throttle, 0.0..=1.0)?;
+        check("brake", self.brake, 0.0..=1.0)?;
+        check("parkingbrake", self.parkingbrake, 0.0..=1.0)?;
+        check("clutch", self.clutch, 0.0..=1.0)?;
+        match self.gear {
+            Some(g) if !GEAR_RANGE.contains(&g) => Err(BngError::ValueError(format!(
+                "gear must be in [{}, {}], got {g}",
+                GEAR_RANGE.start(),
+                GEAR_RANGE.end()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// The fields of a `Control` request.
+    pub(crate) fn fields(&self) -> Vec<(&'static str, rmpv::Value)> {
+        let mut fields = Vec::new();
+        let analog = [
+            ("steering", self.steering),
+            ("throttle", self.throttle),
+            ("brake", self.brake),
+            ("parkingbrake", self.parkingbrake),
+            ("clutch", self.clutch),
+        ];
+        for (name, value) in analog {
+            if let Some(v) = value {
+                fields.push((name, rmpv::Value::from(v)));
+            }
+        }
+        if let Some(g) = self.gear {
+            fields.push(("gear", rmpv::Value::from(g)));
+        }
+        fields
+    }
+}
+
+/// Client-side shaping of [`VehicleInputs`] sent over several steps.
+///
+/// Set the desired inputs with [`set`](Self::set) whenever they change, and call
+/// [`apply`](Self::apply) once per simulation step with the simulated time since the
+/// previous call. Steering and the pedals are optionally smoothed with a first-order
+/// low-pass filter and then rate limited; the gear changes immediately.
+///
+/// Without hold mode, `apply` only sends the inputs that changed. With
+/// [`with_hold`](Self::with_hold) it re-sends the full set every call, so the vehicle
+/// sees the same inputs every step even if the simulator resets them in between.
+///
+/// The first value of each input is sent as is, since the vehicle's current input
+/// is unknown.
+///
+/// # Example
+/// ```no_run
+/// # async fn example(bng: &mut beamng_rs::BeamNg, ego: &mut beamng_rs::vehicle::Vehicle) -> beamng_proto::Result<()> {
+/// use beamng_rs::api::vehicle::{InputController, VehicleInputs};
+///
+/// let mut inputs = InputController::new().with_steering_rate(2.0).with_hold(true);
+/// inputs.set(VehicleInputs::new().steering(0.8).throttle(0.3))?;
+/// for _ in 0..100 {
+///     inputs.apply(ego, 0.05).await?;
+///     bng.control().step(3, true).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InputController {
+    target: VehicleInputs,
+    current: VehicleInputs,
+    steering_rate: Option<f64>,
+    pedal_rate: Option<f64>,
+    smoothing: Option<f64>,
+    hold: bool,
+}
+
+impl InputController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit steering changes to `rate` per second.
+    pub fn with_steering_rate(mut self, rate: f64) -> Self {
+        self.steering_rate = Some(rate);
+        self
+    }
+
+    /// Limit throttle, brake, parking brake and clutch changes to `rate` per second.
+    pub fn with_pedal_rate(mut self, rate: f64) -> Self {
+        self.pedal_rate = Some(rate);
+        self
+    }
+
+    /// Smooth steering and the pedals with a low-pass filter of the given time
+    /// constant in seconds.
+    pub fn with_smoothing(mut self, time_constant: f64) -> Self {
+        self.smoothing = Some(time_constant);
+        self
+    }
+
+    /// Re-send every input on each [`apply`](Self::apply), not only those that changed.
+    pub fn with_hold(mut self, hold: bool) -> Self {
+        self.hold = hold;
+        self
+    }
+
+    /// Validate and merge new desired inputs into the target.
+    pub fn set(&mut self, inputs: VehicleInputs) -> Result<()> {
+        inputs.validate()?;
+        self.target.merge(&inputs);
+        Ok(())
+    }
+
+    /// The desired inputs.
+    pub fn target(&self) -> &VehicleInputs {
+        &self.target
+    }
+
+    /// The shaped value of every input as of the last [`next`](Self::next).
+    pub fn current(&self) -> &VehicleInputs {
+        &self.current
+    }
+
+    /// Move one input towards its target over `dt` seconds, returning the new value
+    /// if it should be sent.
+    fn shape(
+        target: Option<f64>,
+        current: &mut Option<f64>,
+        rate: Option<f64>,
+        smoothing: Option<f64>,
+        dt: f64,
+        hold: bool,
+    ) -> Option<f64> {
+        let target = target?;
+        let next = match *current {
+            None => target,
+            Some(c) => {
+                let mut v = match smoothing {
+                    Some(tau) if tau > 0.0 => c + (target - c) * (1.0 - (-dt / tau).exp()),
+                    _ => target,
+                };
+                if let Some(rate) = rate {
+                    let max_step = rate * dt;
+                    v = c + (v - c).clamp(-max_step, max_step);
+                }
+                v
+            }
+        };
+        let changed = *current != Some(next);
+        *current = Some(next);
+        (hold || changed).then_some(next)
+    }
+
+    /// Advance the shaped inputs by `dt` seconds and return those to send.
+    pub fn next(&mut self, dt: f64) -> VehicleInputs {
+        let dt = dt.max(0.0);
+        let (hold, smoothing) = (self.hold, self.smoothing);
+        let (t, c) = (&self.target, &mut self.current);
+        let gear_changed = t.gear.is_some() && c.gear != t.gear;
+        c.gear = t.gear.or(c.gear);
+        VehicleInputs {
+            steering: Self::shape(
+                t.steering,
+                &mut c.steering,
+                self.steering_rate,
+                smoothing,
+                dt,
+                hold,
+            ),
+            throttle: Self::shape(
+                t.throttle,
+                &mut c.throttle,
+                self.pedal_rate,
+                smoothing,
+                dt,
+                hold,
+            ),
+            brake: Self::shape(t.brake, &mut c.brake, self.pedal_rate, smoothing, dt, hold),
+            parkingbrake: Self::shape(
+                t.parkingbrake,
+                &mut c.parkingbrake,
+                self.pedal_rate,
+                smoothing,
+                dt,
+                hold,
+            ),
+            clutch: Self::shape(
+                t.clutch,
+                &mut c.clutch,
+                self.pedal_rate,
+                smoothing,
+                dt,
+                hold,
+            ),
+            gear: c.gear.filter(|_| hold || gear_changed),
+        }
+    }
+
+    /// Advance the shaped inputs by `dt` seconds and send them to the vehicle.
+    pub async fn apply(&mut self, vehicle: &mut Vehicle, dt: f64) -> Result<()> {
+        let inputs = self.next(dt);
+        if inputs.is_empty() {
+            return Ok(());
+        }
+        vehicle.root().control(&inputs).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_rate_limit_and_hold() {
+        assert!(VehicleInputs::new().steering(1.5).validate().is_err());
+        assert!(VehicleInputs::new().brake(-0.1).validate().is_err());
+        assert!(VehicleInputs::new().gear(17).validate().is_err());
+        assert!(VehicleInputs::new().throttle(f64::NAN).validate().is_err());
+
+        let mut ctl = InputController::new().with_steering_rate(1.0);
+        ctl.set(VehicleInputs::new().steering(0.0).gear(1)).unwrap();
+        assert_eq!(ctl.next(0.1), VehicleInputs::new().steering(0.0).gear(1));
+        ctl.set(VehicleInputs::new().steering(0.5)).unwrap();
+        let sent = ctl.next(0.1);
+        assert!((sent.steering.unwrap() - 0.1).abs() < 1e-9);
+        assert_eq!(sent.gear, None);
+
+        // Without hold, unchanged inputs are not re-sent.
+        let mut ctl = InputController::new();
+        ctl.set(VehicleInputs::new().throttle(0.4)).unwrap();
+        assert!(!ctl.next(0.1).is_empty());
+        assert!(ctl.next(0.1).is_empty());
+        let mut ctl = ctl.with_hold(true);
+        assert_eq!(ctl.next(0.1), VehicleInputs::new().throttle(0.4));
+    }
+}
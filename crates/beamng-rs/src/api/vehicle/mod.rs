@@ -1,5 +1,13 @@
 mod ai;
+mod controls;
+mod inputs;
+mod parts;
 mod root;
 
-pub use ai::AIApi;
+pub use ai::{
+    load_script, AIApi, AiConfig, AiMode, AiState, LinePoint, ScriptPoint, SpeedMode, TargetMode,
+};
+pub use controls::{ControlsApi, Headlights, LightBar, Lights, ShiftMode};
+pub use inputs::{InputController, VehicleInputs, GEAR_RANGE};
+pub use parts::{Change, PartConfig, PartConfigDiff, PartOptions};
 pub use root::RootApi;
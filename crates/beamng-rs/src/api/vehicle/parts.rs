@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use beamng_proto::{BngError, Result};
+use serde::{Deserialize, Serialize};
+
+/// The parts that fit each slot of a vehicle, as returned by
+/// [`ControlsApi::get_part_options`](super::ControlsApi::get_part_options).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PartOptions {
+    /// Slot name to the names of the parts that can go in it.
+    pub slots: BTreeMap<String, Vec<String>>,
+}
+
+impl PartOptions {
+    /// Parse the `options` map of a `GetPartOptions` response. Each slot maps either
+    /// to a list of part names or to a map keyed by part name.
+    pub(crate) fn from_value(value: &rmpv::Value) -> Self {
+        let mut slots = BTreeMap::new();
+        for (slot, parts) in value.as_map().into_iter().flatten() {
+            let Some(slot) = slot.as_str() else {
+                continue;
+            };
+            let names = match parts {
+                rmpv::Value::Array(parts) => parts
+                    .iter()
+                    .filter_map(|p| p.as_str().map(str::to_string))
+                    .collect(),
+                rmpv::Value::Map(parts) => parts
+                    .iter()
+                    .filter_map(|(p, _)| p.as_str().map(str::to_string))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            slots.insert(slot.to_string(), names);
+        }
+        Self { slots }
+    }
+
+    /// Check that every slot of `config` exists and holds one of its available parts
+    /// or is empty.
+    pub fn check(&self, config: &PartConfig) -> Result<()> {
+        for (slot, part) in &config.parts {
+            let available = self.slots.get(slot).ok_or_else(|| {
+                BngError::ValueError(format!("Vehicle has no part slot \"{slot}\""))
+            })?;
+            if !part.is_empty() && !available.contains(part) {
+                return Err(BngError::ValueError(format!(
+                    "Part \"{part}\" does not fit slot \"{slot}\""
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A vehicle's part configuration: which part is in each slot and the tuning
+/// variables, in the layout of BeamNG `.pc` files.
+///
+/// Fields of a `.pc` file other than `parts` and `vars`, such as `format`, `model`
+/// or `paints`, are kept in [`other`](Self::other) so a loaded file saves unchanged.
+///
+/// # Example
+/// ```no_run
+/// # async fn example(bng: &mut beamng_rs::BeamNg, ego: &mut beamng_rs::vehicle::Vehicle) -> beamng_proto::Result<()> {
+/// let base = ego.controls().get_part_config().await?;
+/// let stiff = base
+///     .clone()
+///     .with_part("etk800_spring_F", "etk800_spring_F_sport")
+///     .with_var("$springheight_F", -0.02);
+/// println!("{}", base.diff(&stiff));
+/// ego.controls().set_part_config(bng, &stiff).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PartConfig {
+    /// Slot name to the name of the part in it, or an empty string for an empty slot.
+    #[serde(default)]
+    pub parts: BTreeMap<String, String>,
+    /// Tuning variable name, such as `$springheight_F`, to its value.
+    #[serde(default)]
+    pub vars: BTreeMap<String, f64>,
+    /// Every other top-level field of the `.pc` file, such as `format`, `model` or
+    /// `paints`, kept verbatim.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl PartConfig {
+    /// Parse the `config` map of a `GetPartConfig` response.
+    pub(crate) fn from_value(value: rmpv::Value) -> Result<Self> {
+        rmpv::ext::from_value(value)
+            .map_err(|e| BngError::ValueError(format!("Invalid part config: {e}")))
+    }
+
+    /// Encode as the `config` field of a `SetPartConfig` request.
+    pub(crate) fn to_value(&self) -> Result<rmpv::Value> {
+        rmpv::ext::to_value(self)
+            .map_err(|e| BngError::ValueError(format!("Cannot encode part config: {e}")))
+    }
+
+    /// Load a `.pc` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let text = std::fs::read_to_string(path.as_ref())?;
+        serde_json::from_str(&text).map_err(|e| {
+            BngError::ValueError(format!(
+                "Invalid part config {}: {e}",
+                path.as_ref().display()
+            ))
+        })
+    }
+
+    /// Save as a `.pc` file, e.g. to spawn with
+    /// [`VehicleBuilder::part_config`](crate::vehicle::VehicleBuilder::part_config).
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let text = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Put `part` in `slot`, adding the slot if it is not listed yet. An empty `part`
+    /// leaves the slot empty.
+    pub fn with_part(mut self, slot: impl Into<String>, part: impl Into<String>) -> Self {
+        self.parts.insert(slot.into(), part.into());
+        self
+    }
+
+    /// Set the tuning variable `name`, including its leading `$`, to `value`.
+    pub fn with_var(mut self, name: impl Into<String>, value: f64) -> Self {
+        self.vars.insert(name.into(), value);
+        self
+    }
+
+    /// The changes that turn `self` into `other`.
+    pub fn diff(&self, other: &PartConfig) -> PartConfigDiff {
+        PartConfigDiff {
+            parts: diff_maps(&self.parts, &other.parts),
+            vars: diff_maps(&self.vars, &other.vars),
+        }
+    }
+}
+
+/// One entry that differs between two maps: its key and its value on each side,
+/// `None` where it is absent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change<T> {
+    /// The slot or variable name.
+    pub key: String,
+    /// The value in the config [`diff`](PartConfig::diff) was called on.
+    pub from: Option<T>,
+    /// The value in the config passed to [`diff`](PartConfig::diff).
+    pub to: Option<T>,
+}
+
+fn diff_maps<T: Clone + PartialEq>(
+    a: &BTreeMap<String, T>,
+    b: &BTreeMap<String, T>,
+) -> Vec<Change<T>> {
+    let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter_map(|key| {
+            let (from, to) = (a.get(key), b.get(key));
+            (from != to).then(|| Change {
+                key: key.clone(),
+                from: from.cloned(),
+                to: to.cloned(),
+            })
+        })
+        .collect()
+}
+
+/// The differences between two [`PartConfig`]s, sorted by slot and variable name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PartConfigDiff {
+    /// Slots whose part was added, removed or swapped.
+    pub parts: Vec<Change<String>>,
+    /// Tuning variables that were added, removed or changed.
+    pub vars: Vec<Change<f64>>,
+}
+
+impl PartConfigDiff {
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty() && self.vars.is_empty()
+    }
+}
+
+impl fmt::Display for PartConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn side<T: fmt::Display>(v: &Option<T>) -> String {
+            v.as_ref().map_or("-".into(), |v| v.to_string())
+        }
+        for c in &self.parts {
+            writeln!(f, "part {}: {} -> {}", c.key, side(&c.from), side(&c.to))?;
+        }
+        for c in &self.vars {
+            writeln!(f, "var {}: {} -> {}", c.key, side(&c.from), side(&c.to))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_config_roundtrip_and_diff() {
+        let pc = r#"{"format": 2, "model": "etk800",
+            "parts": {"etk800_spring_F": "etk800_spring_F", "etk800_body": "etk800_body_wagon"},
+            "vars": {"$springheight_F": 0, "$tirepressure_F": 30.5}}"#;
+        let base: PartConfig = serde_json::from_str(pc).unwrap();
+        assert_eq!(base.other["model"], "etk800");
+        let value = base.to_value().unwrap();
+        assert_eq!(PartConfig::from_value(value).unwrap(), base);
+        assert!(PartConfig::from_value(rmpv::Value::Nil).is_err());
+
+        let tuned = base
+            .clone()
+            .with_part("etk800_spring_F", "etk800_spring_F_sport")
+            .with_var("$springheight_F", -0.02)
+            .with_var("$arb_spring_F", 12000.0);
+        let diff = base.diff(&tuned);
+        assert_eq!(diff.parts.len(), 1);
+        assert_eq!(diff.parts[0].to.as_deref(), Some("etk800_spring_F_sport"));
+        let vars: Vec<_> = diff.vars.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(vars, vec!["$arb_spring_F", "$springheight_F"]);
+        assert!(tuned.diff(&tuned).is_empty());
+
+        let options = PartOptions {
+            slots: BTreeMap::from([(
+                "etk800_spring_F".to_string(),
+                vec!["etk800_spring_F".to_string()],
+            )]),
+        };
+        assert!(options.check(&tuned).is_err());
+    }
+}
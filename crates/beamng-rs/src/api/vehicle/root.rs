@@ -1,6 +1,7 @@
 use beamng_proto::types::{Quat, StrDict, Vec3};
 use beamng_proto::Result;
 
+use super::VehicleInputs;
 use crate::vehicle::Vehicle;
 
 /// Root-level vehicle API for direct vehicle control and info.
@@ -43,37 +44,15 @@ impl RootApi<'_> {
             .await
     }
 
-    /// Apply vehicle input (steering, throttle, brake, etc.).
-    pub async fn control(
-        &mut self,
-        steering: Option<f64>,
-        throttle: Option<f64>,
-        brake: Option<f64>,
-        parkingbrake: Option<f64>,
-        clutch: Option<f64>,
-        gear: Option<i32>,
-    ) -> Result<()> {
-        let mut fields: Vec<(&str, rmpv::Value)> = Vec::new();
-        if let Some(v) = steering {
-            fields.push(("steering", rmpv::Value::from(v)));
-        }
-        if let Some(v) = throttle {
-            fields.push(("throttle", rmpv::Value::from(v)));
-        }
-        if let Some(v) = brake {
-            fields.push(("brake", rmpv::Value::from(v)));
-        }
-        if let Some(v) = parkingbrake {
-            fields.push(("parkingbrake", rmpv::Value::from(v)));
-        }
-        if let Some(v) = clutch {
-            fields.push(("clutch", rmpv::Value::from(v)));
-        }
-        if let Some(v) = gear {
-            fields.push(("gear", rmpv::Value::from(v)));
-        }
+    /// Apply driver inputs, leaving those not set unchanged.
+    ///
+    /// The inputs are [validated](VehicleInputs::validate) before sending. For
+    /// smoothing, rate limiting or holding inputs across steps, see
+    /// [`InputController`](super::InputController).
+    pub async fn control(&mut self, inputs: &VehicleInputs) -> Result<()> {
+        inputs.validate()?;
         self.vehicle
-            .send_vehicle_request("Control", &fields)
+            .send_vehicle_request("Control", &inputs.fields())
             .await?;
         Ok(())
     }
@@ -1,10 +1,21 @@
+use std::sync::MutexGuard;
+
 use beamng_proto::{BngError, Connection, Result};
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::api::beamng::*;
+use crate::sensors::registry::{close_sensors, SensorRegistry, SharedSensorRegistry};
+use crate::sensors::{SensorInfo, SensorKind};
 
 /// The main handle to a BeamNG.tech simulator instance.
 ///
+/// Sensors opened through this handle are tracked by name (see
+/// [`list_sensors`](Self::list_sensors)). Sensors attached to a vehicle are closed when
+/// it is despawned with [`VehiclesApi::despawn`]. Sensor handles dropped without being
+/// closed are queued and closed before the next [`ControlApi::step`]. Sensors still
+/// open on [`disconnect`](Self::disconnect) or drop are closed on a best-effort basis;
+/// await [`close_all_sensors`](Self::close_all_sensors) first to see the results.
+///
 /// # Example
 /// ```no_run
 /// # async fn example() -> beamng_proto::Result<()> {
@@ -21,6 +32,7 @@ pub struct BeamNg {
     host: String,
     port: u16,
     connection: Option<Connection>,
+    sensors: SharedSensorRegistry,
 }
 
 impl BeamNg {
@@ -31,6 +43,7 @@ impl BeamNg {
             host: host.into(),
             port,
             connection: None,
+            sensors: SharedSensorRegistry::default(),
         }
     }
 
@@ -48,6 +61,83 @@ impl BeamNg {
             .ok_or_else(|| BngError::Disconnected("Not connected to BeamNG.tech".into()))
     }
 
+    /// Lock the sensor registry.
+    pub(crate) fn sensor_registry(&self) -> MutexGuard<'_, SensorRegistry> {
+        self.sensors.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// A shared handle to the sensor registry, for sensor handles that close on drop.
+    pub(crate) fn shared_sensor_registry(&self) -> SharedSensorRegistry {
+        self.sensors.clone()
+    }
+
+    /// Fail if a sensor with this name is already open.
+    pub(crate) fn check_sensor_name(&self, name: &str) -> Result<()> {
+        self.sensor_registry().check_name(name)
+    }
+
+    pub(crate) fn register_sensor(&self, kind: SensorKind, name: &str, vid: Option<&str>) {
+        self.sensor_registry().insert(SensorInfo {
+            name: name.to_string(),
+            kind,
+            vid: vid.map(str::to_string),
+        });
+    }
+
+    /// Close one sensor, which stays registered unless the simulator acknowledges.
+    ///
+    /// Fails with [`BngError::ValueError`] if the sensor is not open, e.g. because
+    /// [`close_all_sensors`](Self::close_all_sensors) or [`VehiclesApi::despawn`]
+    /// already closed it.
+    pub(crate) async fn close_sensor(&mut self, name: &str) -> Result<()> {
+        let sensor = self
+            .sensor_registry()
+            .get(name)
+            .ok_or_else(|| BngError::ValueError(format!("Sensor \"{name}\" is not open")))?;
+        sensor.close(self.conn()?).await?;
+        self.sensor_registry().remove(name);
+        info!("Closed {:?}: \"{name}\"", sensor.kind);
+        Ok(())
+    }
+
+    /// List the sensors currently open through this handle.
+    pub fn list_sensors(&self) -> Vec<SensorInfo> {
+        self.sensor_registry().list()
+    }
+
+    /// Close the sensors whose handles were dropped without being closed.
+    ///
+    /// This runs automatically before each [`ControlApi::step`]. Failures are logged
+    /// rather than returned, since the sensor may already be gone on the simulator side.
+    pub async fn flush_pending_closes(&mut self) -> Result<()> {
+        let pending = self.sensor_registry().take_pending();
+        if !pending.is_empty() {
+            close_sensors(self.conn()?, pending).await;
+        }
+        Ok(())
+    }
+
+    /// Close the sensors attached to a vehicle.
+    pub async fn close_vehicle_sensors(&mut self, vid: &str) -> Result<()> {
+        let sensors = self.sensor_registry().take_vehicle(vid);
+        if !sensors.is_empty() {
+            close_sensors(self.conn()?, sensors).await;
+        }
+        Ok(())
+    }
+
+    /// Close every open sensor. Handles to closed sensors must not be used afterwards.
+    ///
+    /// This is the shutdown path: await it before [`disconnect`](Self::disconnect) or
+    /// dropping the handle, neither of which closes sensors.
+    pub async fn close_all_sensors(&mut self) -> Result<()> {
+        let sensors = self.sensor_registry().take_all();
+        if !sensors.is_empty() {
+            close_sensors(self.conn()?, sensors).await;
+        }
+        Ok(())
+    }
+
     /// Returns the host address.
     pub fn host(&self) -> &str {
         &self.host
@@ -59,8 +149,27 @@ impl BeamNg {
     }
 
     /// Disconnect from the simulator.
+    ///
+    /// Sensors still open are closed on a best-effort basis: the close requests are
+    /// sent from a task on the current Tokio runtime, which drops the connection once
+    /// they are answered, and failures are only logged. Outside a runtime the sensors
+    /// cannot be closed and a warning is logged instead.
     pub fn disconnect(&mut self) {
-        self.connection = None;
+        let Some(mut conn) = self.connection.take() else {
+            return;
+        };
+        let sensors = self.sensor_registry().take_all();
+        if !sensors.is_empty() {
+            match tokio::runtime::Handle::try_current() {
+                Ok(runtime) => {
+                    runtime.spawn(async move { close_sensors(&mut conn, sensors).await });
+                }
+                Err(_) => warn!(
+                    "Disconnecting outside a Tokio runtime with {} sensors still open",
+                    sensors.len()
+                ),
+            }
+        }
         info!("Disconnected from BeamNG.tech");
     }
 
@@ -116,3 +225,88 @@ impl BeamNg {
         UiApi { bng: self }
     }
 }
+
+impl Drop for BeamNg {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{mock_sim, req_type};
+
+    #[tokio::test]
+    async fn test_close_sensors_on_shutdown() {
+        // Closing "gps" fails once, then succeeds.
+        let mut gps_failures = 1;
+        let (port, sim) = mock_sim(move |req| match req_type(req) {
+            "CloseGPS" if gps_failures > 0 => {
+                gps_failures -= 1;
+                vec![("bngError", rmpv::Value::from("busy"))]
+            }
+            t => vec![("type", rmpv::Value::from(t.replace("Close", "Closed")))],
+        })
+        .await;
+        let mut bng = BeamNg::new("127.0.0.1", port).connect().await.unwrap();
+        bng.register_sensor(SensorKind::Gps, "gps", Some("ego"));
+        bng.register_sensor(SensorKind::Camera, "cam", None);
+
+        // A failed close leaves the sensor registered; an unknown one is an error.
+        assert!(bng.close_sensor("gps").await.is_err());
+        assert_eq!(bng.list_sensors().len(), 2);
+        assert!(matches!(
+            bng.close_sensor("missing").await,
+            Err(BngError::ValueError(_))
+        ));
+
+        bng.close_all_sensors().await.unwrap();
+        assert!(bng.list_sensors().is_empty());
+        bng.disconnect();
+
+        let types: Vec<_> = sim
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| req_type(r).to_string())
+            .collect();
+        assert_eq!(types, vec!["CloseGPS", "CloseGPS", "CloseCamera"]);
+    }
+
+    async fn requests_after_shutdown(drop_handle: bool) -> Vec<String> {
+        let (port, sim) = mock_sim(|req| {
+            vec![(
+                "type",
+                rmpv::Value::from(req_type(req).replace("Close", "Closed")),
+            )]
+        })
+        .await;
+        let mut bng = BeamNg::new("127.0.0.1", port).connect().await.unwrap();
+        bng.register_sensor(SensorKind::Gps, "gps", Some("ego"));
+        bng.register_sensor(SensorKind::Camera, "cam", None);
+        if drop_handle {
+            drop(bng);
+        } else {
+            bng.disconnect();
+            assert!(bng.list_sensors().is_empty());
+        }
+        sim.await
+            .unwrap()
+            .iter()
+            .map(|r| req_type(r).to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_and_drop_close_open_sensors() {
+        assert_eq!(
+            requests_after_shutdown(false).await,
+            vec!["CloseGPS", "CloseCamera"]
+        );
+        assert_eq!(
+            requests_after_shutdown(true).await,
+            vec!["CloseGPS", "CloseCamera"]
+        );
+    }
+}
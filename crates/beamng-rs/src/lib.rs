@@ -1,9 +1,12 @@
 pub mod api;
 pub mod beamng;
+pub mod lua;
+#[cfg(test)]
+mod mock;
 pub mod scenario;
 pub mod sensors;
 pub mod vehicle;
 
 pub use beamng::BeamNg;
 pub use beamng_proto::{BngError, Result};
-pub use scenario::Scenario;
+pub use scenario::{LoadedScenario, Scenario};
@@ -0,0 +1,417 @@
+//! Helpers for running Lua in the game engine and vehicle VMs.
+//!
+//! [`ToLua`] writes Rust values as Lua literals, with strings escaped, so chunks can
+//! be assembled from user data without breaking the script. [`call`] and [`Chunk`]
+//! compose those literals into function calls and multi-statement chunks.
+//!
+//! ```
+//! use beamng_rs::lua::{self, Chunk};
+//!
+//! let vid = "ego \"1\"";
+//! let chunk = Chunk::new()
+//!     .push(lua::call("local veh = scenetree.findObject").arg(vid))
+//!     .push(lua::call("veh:setPosition").arg(lua::raw("vec3(0, 0, 0)")))
+//!     .to_string();
+//! assert_eq!(
+//!     chunk,
+//!     "local veh = scenetree.findObject(\"ego \\\"1\\\"\")\nveh:setPosition(vec3(0, 0, 0))"
+//! );
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Write};
+
+use beamng_proto::{BngError, Result};
+use serde::de::DeserializeOwned;
+
+const KEYWORDS: [&str; 22] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+/// Values that can be written as a Lua expression.
+pub trait ToLua {
+    /// Append the Lua expression for this value to `out`.
+    fn write_lua(&self, out: &mut String);
+
+    /// The Lua expression for this value.
+    fn to_lua(&self) -> String {
+        let mut out = String::new();
+        self.write_lua(&mut out);
+        out
+    }
+}
+
+/// Quote a string as a Lua string literal.
+pub fn quote(s: &str) -> String {
+    s.to_lua()
+}
+
+/// Whether `name` can be used as a bare Lua identifier or table key.
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+impl ToLua for str {
+    fn write_lua(&self, out: &mut String) {
+        out.push('"');
+        for c in self.chars() {
+            match c {
+                '\\' => out.push_str("\\\\"),
+                '"' => out.push_str("\\\""),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                // A decimal escape followed by a digit would absorb it, so pad to three.
+                c if c.is_ascii_control() => {
+                    let _ = write!(out, "\\{:03}", c as u32);
+                }
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+}
+
+impl ToLua for String {
+    fn write_lua(&self, out: &mut String) {
+        self.as_str().write_lua(out)
+    }
+}
+
+impl ToLua for bool {
+    fn write_lua(&self, out: &mut String) {
+        out.push_str(if *self { "true" } else { "false" });
+    }
+}
+
+macro_rules! int_to_lua {
+    ($($t:ty),*) => {$(
+        impl ToLua for $t {
+            fn write_lua(&self, out: &mut String) {
+                let _ = write!(out, "{self}");
+            }
+        }
+    )*};
+}
+
+int_to_lua!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl ToLua for f64 {
+    fn write_lua(&self, out: &mut String) {
+        if self.is_nan() {
+            out.push_str("(0/0)");
+        } else if self.is_infinite() {
+            out.push_str(if *self > 0.0 {
+                "math.huge"
+            } else {
+                "-math.huge"
+            });
+        } else {
+            let _ = write!(out, "{self}");
+        }
+    }
+}
+
+impl ToLua for f32 {
+    fn write_lua(&self, out: &mut String) {
+        f64::from(*self).write_lua(out)
+    }
+}
+
+impl<T: ToLua> ToLua for Option<T> {
+    fn write_lua(&self, out: &mut String) {
+        match self {
+            Some(v) => v.write_lua(out),
+            None => out.push_str("nil"),
+        }
+    }
+}
+
+impl<T: ToLua + ?Sized> ToLua for &T {
+    fn write_lua(&self, out: &mut String) {
+        (**self).write_lua(out)
+    }
+}
+
+/// Write items as a Lua sequence table.
+fn write_array<'a, T: ToLua + 'a>(items: impl IntoIterator<Item = &'a T>, out: &mut String) {
+    out.push('{');
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        item.write_lua(out);
+    }
+    out.push('}');
+}
+
+impl<T: ToLua> ToLua for [T] {
+    fn write_lua(&self, out: &mut String) {
+        write_array(self, out)
+    }
+}
+
+impl<T: ToLua, const N: usize> ToLua for [T; N] {
+    fn write_lua(&self, out: &mut String) {
+        write_array(self, out)
+    }
+}
+
+impl<T: ToLua> ToLua for Vec<T> {
+    fn write_lua(&self, out: &mut String) {
+        write_array(self, out)
+    }
+}
+
+macro_rules! tuple_to_lua {
+    ($($name:ident . $idx:tt),+) => {
+        impl<$($name: ToLua),+> ToLua for ($($name,)+) {
+            fn write_lua(&self, out: &mut String) {
+                out.push('{');
+                $(
+                    if $idx > 0 {
+                        out.push_str(", ");
+                    }
+                    self.$idx.write_lua(out);
+                )+
+                out.push('}');
+            }
+        }
+    };
+}
+
+// Covers `Float2`, `Vec3`, `Quat` and `Color`.
+tuple_to_lua!(A.0, B.1);
+tuple_to_lua!(A.0, B.1, C.2);
+tuple_to_lua!(A.0, B.1, C.2, D.3);
+
+/// Write `key = value` pairs as a Lua table, quoting keys that are not identifiers.
+fn write_table<'a, K, V>(entries: impl IntoIterator<Item = (&'a K, &'a V)>, out: &mut String)
+where
+    K: AsRef<str> + 'a,
+    V: ToLua + 'a,
+{
+    out.push('{');
+    for (i, (key, value)) in entries.into_iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        let key = key.as_ref();
+        if is_identifier(key) {
+            out.push_str(key);
+        } else {
+            out.push('[');
+            key.write_lua(out);
+            out.push(']');
+        }
+        out.push_str(" = ");
+        value.write_lua(out);
+    }
+    out.push('}');
+}
+
+impl<K: AsRef<str>, V: ToLua> ToLua for BTreeMap<K, V> {
+    fn write_lua(&self, out: &mut String) {
+        write_table(self, out)
+    }
+}
+
+/// Entries are written in the map's iteration order, which is unspecified.
+impl<K: AsRef<str>, V: ToLua> ToLua for HashMap<K, V> {
+    fn write_lua(&self, out: &mut String) {
+        write_table(self, out)
+    }
+}
+
+impl ToLua for rmpv::Value {
+    fn write_lua(&self, out: &mut String) {
+        match self {
+            rmpv::Value::Nil => out.push_str("nil"),
+            rmpv::Value::Boolean(b) => b.write_lua(out),
+            rmpv::Value::Integer(i) => {
+                let _ = write!(out, "{i}");
+            }
+            rmpv::Value::F32(f) => f.write_lua(out),
+            rmpv::Value::F64(f) => f.write_lua(out),
+            rmpv::Value::String(s) => String::from_utf8_lossy(s.as_bytes()).write_lua(out),
+            rmpv::Value::Binary(b) => String::from_utf8_lossy(b).write_lua(out),
+            rmpv::Value::Array(items) => write_array(items, out),
+            rmpv::Value::Map(entries) => {
+                out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    match key.as_str() {
+                        Some(k) if is_identifier(k) => out.push_str(k),
+                        _ => {
+                            out.push('[');
+                            key.write_lua(out);
+                            out.push(']');
+                        }
+                    }
+                    out.push_str(" = ");
+                    value.write_lua(out);
+                }
+                out.push('}');
+            }
+            rmpv::Value::Ext(_, _) => out.push_str("nil"),
+        }
+    }
+}
+
+impl ToLua for std::borrow::Cow<'_, str> {
+    fn write_lua(&self, out: &mut String) {
+        self.as_ref().write_lua(out)
+    }
+}
+
+/// A Lua expression written verbatim, such as a variable or `vec3(1, 2, 3)`.
+///
+/// Never build one from untrusted input; it is not escaped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Raw(pub String);
+
+/// A Lua expression written verbatim, see [`Raw`].
+pub fn raw(expr: impl Into<String>) -> Raw {
+    Raw(expr.into())
+}
+
+impl ToLua for Raw {
+    fn write_lua(&self, out: &mut String) {
+        out.push_str(&self.0);
+    }
+}
+
+/// A Lua function call with escaped arguments, built with [`call`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    function: String,
+    args: Vec<String>,
+}
+
+/// Start a call to `function`, a Lua expression such as `ai.setMode` or `obj:getID`.
+pub fn call(function: impl Into<String>) -> Call {
+    Call {
+        function: function.into(),
+        args: Vec::new(),
+    }
+}
+
+impl Call {
+    /// Append an argument.
+    pub fn arg(mut self, value: impl ToLua) -> Self {
+        self.args.push(value.to_lua());
+        self
+    }
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.function, self.args.join(", "))
+    }
+}
+
+impl ToLua for Call {
+    fn write_lua(&self, out: &mut String) {
+        let _ = write!(out, "{self}");
+    }
+}
+
+/// A sequence of Lua statements, optionally ending in a `return`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Chunk {
+    statements: Vec<String>,
+}
+
+impl Chunk {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a statement, such as a [`Call`].
+    pub fn push(mut self, statement: impl fmt::Display) -> Self {
+        self.statements.push(statement.to_string());
+        self
+    }
+
+    /// Append `name = value`.
+    pub fn assign(self, name: &str, value: impl ToLua) -> Self {
+        let statement = format!("{name} = {}", value.to_lua());
+        self.push(statement)
+    }
+
+    /// Append `return value`.
+    pub fn returning(self, value: impl ToLua) -> Self {
+        let statement = format!("return {}", value.to_lua());
+        self.push(statement)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+}
+
+impl fmt::Display for Chunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.statements.join("\n"))
+    }
+}
+
+/// Decode the value returned by a Lua chunk, treating no value as `nil`.
+pub(crate) fn decode_result<T: DeserializeOwned>(value: Option<rmpv::Value>) -> Result<T> {
+    rmpv::ext::from_value(value.unwrap_or(rmpv::Value::Nil))
+        .map_err(|e| BngError::ValueError(format!("Cannot decode Lua result: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_result() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Gear {
+            index: i32,
+            ratio: f64,
+        }
+        let value = rmpv::Value::Map(vec![
+            (rmpv::Value::from("index"), rmpv::Value::from(2)),
+            (rmpv::Value::from("ratio"), rmpv::Value::from(1.8)),
+        ]);
+        let gear: Gear = decode_result(Some(value)).unwrap();
+        assert_eq!(
+            gear,
+            Gear {
+                index: 2,
+                ratio: 1.8
+            }
+        );
+        assert_eq!(decode_result::<Option<f64>>(None).unwrap(), None);
+        assert!(decode_result::<Gear>(Some(rmpv::Value::from("oops"))).is_err());
+    }
+
+    #[test]
+    fn test_lua_literals() {
+        assert_eq!(quote("it's \"x\"\\\n\u{1}2"), r#""it's \"x\"\\\n\0012""#);
+        assert_eq!((1.5, -2.0, 3.0).to_lua(), "{1.5, -2, 3}");
+        assert_eq!(vec![Some(1), None].to_lua(), "{1, nil}");
+        assert_eq!([f64::INFINITY, f64::NAN].to_lua(), "{math.huge, (0/0)}");
+        let table = BTreeMap::from([("speed", 10.0), ("end", 1.0), ("a b", 2.0)]);
+        assert_eq!(table.to_lua(), r#"{["a b"] = 2, ["end"] = 1, speed = 10}"#);
+        assert_eq!(
+            call("ai.driveUsingPath")
+                .arg(BTreeMap::from([("wpTargetList", vec!["wp\"1"])]))
+                .to_string(),
+            r#"ai.driveUsingPath({wpTargetList = {"wp\"1"}})"#
+        );
+        let chunk = Chunk::new().assign("local x", 1).returning(raw("x + 1"));
+        assert_eq!(chunk.to_string(), "local x = 1\nreturn x + 1");
+    }
+}
@@ -0,0 +1,58 @@
+//! A scripted stand-in for the simulator, for tests that exercise the protocol.
+
+use beamng_proto::connection::PROTOCOL_VERSION;
+use beamng_proto::frame::{read_frame, write_frame};
+use beamng_proto::types::{value_as_str, value_to_str_dict, StrDict};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// The fields of a reply, without `_id`.
+pub(crate) type Reply = Vec<(&'static str, rmpv::Value)>;
+
+/// The type of a request.
+pub(crate) fn req_type(req: &StrDict) -> &str {
+    req.get("type").and_then(value_as_str).unwrap_or("")
+}
+
+/// Start a server that answers the Hello handshake and passes every other request to
+/// `respond`, replying with the returned fields plus the request's `_id`.
+///
+/// Returns the port and a handle that yields the requests received, in order, once
+/// the client disconnects.
+pub(crate) async fn mock_sim(
+    mut respond: impl FnMut(&StrDict) -> Reply + Send + 'static,
+) -> (u16, JoinHandle<Vec<StrDict>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (mut reader, mut writer) = tokio::io::split(stream);
+        let mut received = Vec::new();
+        while let Ok(data) = read_frame(&mut reader).await {
+            let value = rmpv::decode::read_value(&mut &data[..]).unwrap();
+            let req = value_to_str_dict(value).unwrap();
+            let mut reply = if req_type(&req) == "Hello" {
+                vec![
+                    ("type", rmpv::Value::from("Hello")),
+                    ("protocolVersion", rmpv::Value::from(PROTOCOL_VERSION)),
+                ]
+            } else {
+                let reply = respond(&req);
+                received.push(req.clone());
+                reply
+            };
+            reply.push(("_id", req["_id"].clone()));
+            let map = reply
+                .into_iter()
+                .map(|(k, v)| (rmpv::Value::from(k), v))
+                .collect();
+            let mut packed = Vec::new();
+            rmpv::encode::write_value(&mut packed, &rmpv::Value::Map(map)).unwrap();
+            if write_frame(&mut writer, &packed).await.is_err() {
+                break;
+            }
+        }
+        received
+    });
+    (port, handle)
+}
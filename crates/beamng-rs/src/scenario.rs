@@ -1,15 +1,17 @@
+use std::collections::BTreeMap;
+
 use beamng_proto::types::{Quat, Vec3};
 use beamng_proto::{BngError, Result};
 use serde_json::{json, Map, Value as JsonValue};
 
 use crate::beamng::BeamNg;
-use crate::vehicle::VehicleOptions;
+use crate::vehicle::{Vehicle, VehicleOptions};
 
 /// A lightweight vehicle descriptor stored in a [`Scenario`].
 ///
-/// This is intentionally separate from [`Vehicle`](crate::vehicle::Vehicle) to avoid
-/// shared mutable reference complexity. After loading a scenario, vehicles are connected
-/// independently.
+/// This is intentionally separate from [`Vehicle`] to avoid shared mutable reference
+/// complexity. Loading the scenario returns a connected [`Vehicle`] for each descriptor
+/// in a [`LoadedScenario`].
 #[derive(Debug, Clone)]
 pub struct ScenarioVehicle {
     pub vid: String,
@@ -43,6 +45,60 @@ pub struct Scenario {
     uuid: String,
 }
 
+impl ScenarioVehicle {
+    /// A disconnected [`Vehicle`] handle with this descriptor's model and options.
+    pub fn to_vehicle(&self) -> Vehicle {
+        Vehicle {
+            vid: self.vid.clone(),
+            model: self.model.clone(),
+            connection: None,
+            options: self.options.clone(),
+        }
+    }
+}
+
+/// The vehicles of a scenario loaded with
+/// [`ScenarioApi::load_scenario`](crate::api::beamng::ScenarioApi::load_scenario),
+/// each connected and keyed by vid.
+///
+/// # Example
+/// ```no_run
+/// # async fn example(bng: &mut beamng_rs::BeamNg, scenario: &beamng_rs::Scenario) -> beamng_proto::Result<()> {
+/// let mut loaded = bng.scenario().load_scenario(scenario, true).await?;
+/// let mut ego = loaded.take("ego").expect("ego is in the scenario");
+/// ego.ai().set_speed(15.0, beamng_rs::api::vehicle::SpeedMode::Limit).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct LoadedScenario {
+    /// Connected handles for the scenario's vehicles, keyed by vid.
+    pub vehicles: BTreeMap<String, Vehicle>,
+    /// The vid of the vehicle the player controls, if the simulator reports one.
+    pub player_vid: Option<String>,
+}
+
+impl LoadedScenario {
+    pub fn get(&self, vid: &str) -> Option<&Vehicle> {
+        self.vehicles.get(vid)
+    }
+
+    pub fn get_mut(&mut self, vid: &str) -> Option<&mut Vehicle> {
+        self.vehicles.get_mut(vid)
+    }
+
+    /// Remove a vehicle from the map to own its handle.
+    pub fn take(&mut self, vid: &str) -> Option<Vehicle> {
+        self.vehicles.remove(vid)
+    }
+
+    /// The player vehicle, if it is one of the scenario's vehicles.
+    pub fn player(&mut self) -> Option<&mut Vehicle> {
+        let vid = self.player_vid.as_deref()?;
+        self.vehicles.get_mut(vid)
+    }
+}
+
 impl Scenario {
     /// Create a new scenario descriptor for the given level and name.
     pub fn new(level: impl Into<String>, name: impl Into<String>) -> Self {
@@ -80,6 +136,11 @@ impl Scenario {
         self.path.as_deref()
     }
 
+    /// Get the vehicle descriptors of the scenario.
+    pub fn vehicles(&self) -> &[ScenarioVehicle] {
+        &self.vehicles
+    }
+
     /// Get the vehicle IDs in the scenario.
     pub fn vehicle_ids(&self) -> Vec<&str> {
         self.vehicles.iter().map(|v| v.vid.as_str()).collect()
@@ -125,7 +186,7 @@ impl Scenario {
 
         let path = resp
             .get("result")
-            .and_then(|v| beamng_proto::types::value_to_string(v))
+            .and_then(beamng_proto::types::value_to_string)
             .ok_or_else(|| {
                 BngError::ValueError("Missing path in CreateScenario response".into())
             })?;
@@ -134,6 +195,39 @@ impl Scenario {
         Ok(())
     }
 
+    /// Disconnected handles for the scenario's vehicles, checked against the
+    /// `GetCurrentVehicles` response so that a vehicle which failed to spawn is an error
+    /// rather than a connection timeout. A missing response is an error too, unless
+    /// the scenario has no vehicles.
+    pub(crate) fn spawned_vehicles(
+        &self,
+        current: Option<&rmpv::Value>,
+    ) -> Result<BTreeMap<String, Vehicle>> {
+        let spawned: Vec<&str> = match current {
+            Some(rmpv::Value::Map(m)) => m.iter().filter_map(|(k, _)| k.as_str()).collect(),
+            // Lua sends an empty table as an empty array.
+            Some(rmpv::Value::Array(a)) if a.is_empty() => Vec::new(),
+            _ if self.vehicles.is_empty() => Vec::new(),
+            _ => {
+                return Err(BngError::ValueError(format!(
+                    "No vehicle list to check the vehicles of scenario \"{}\" against",
+                    self.name
+                )))
+            }
+        };
+        let mut vehicles = BTreeMap::new();
+        for v in &self.vehicles {
+            if !spawned.contains(&v.vid.as_str()) {
+                return Err(BngError::ValueError(format!(
+                    "Vehicle \"{}\" of scenario \"{}\" was not spawned",
+                    v.vid, self.name
+                )));
+            }
+            vehicles.insert(v.vid.clone(), v.to_vehicle());
+        }
+        Ok(vehicles)
+    }
+
     /// Build the prefab string matching BeamNGpy's format.
     ///
     /// BeamNG's `deserializeLineObjects` reads **one JSON object per line**.
@@ -245,3 +339,37 @@ fn build_footer_json(scenario_name: &str, uuid: &str) -> JsonValue {
     obj.insert("groupPosition".into(), json!("0.000000 0.000000 0.000000"));
     JsonValue::Object(obj)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawned_vehicles() {
+        let mut scenario = Scenario::new("italy", "test");
+        let options = VehicleOptions {
+            license: Some("EGO".into()),
+            ..Default::default()
+        };
+        let (pos, rot) = ((0.0, 0.0, 0.0), (0.0, 0.0, 0.0, 1.0));
+        scenario.add_vehicle("ego", "etk800", pos, rot, options);
+        scenario.add_vehicle("other car", "pickup", pos, rot, VehicleOptions::default());
+
+        let current = rmpv::Value::Map(vec![
+            ("ego".into(), rmpv::Value::Map(vec![])),
+            ("other_car".into(), rmpv::Value::Map(vec![])),
+        ]);
+        let vehicles = scenario.spawned_vehicles(Some(&current)).unwrap();
+        assert_eq!(vehicles["other_car"].model, "pickup");
+        assert_eq!(vehicles["ego"].options.license.as_deref(), Some("EGO"));
+        assert!(!vehicles["ego"].is_connected());
+
+        let partial = rmpv::Value::Map(vec![("ego".into(), rmpv::Value::Map(vec![]))]);
+        assert!(scenario.spawned_vehicles(Some(&partial)).is_err());
+        assert!(scenario.spawned_vehicles(None).is_err());
+        assert!(Scenario::new("italy", "empty")
+            .spawned_vehicles(None)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,292 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use beamng_proto::{BngError, Result};
+
+use super::images::SemanticImage;
+
+/// A 2D bounding box around one object, or one visible region of it, in a camera image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundingBox2D {
+    /// The semantic class of the object: the majority vote over its pixels, with ties
+    /// going to the class listed first in the [`SemanticImage`].
+    pub class: String,
+    /// The object's colour in the instance image.
+    pub instance_colour: [u8; 3],
+    /// Left-most pixel column (inclusive).
+    pub x_min: u32,
+    /// Top-most pixel row (inclusive).
+    pub y_min: u32,
+    /// Right-most pixel column (inclusive).
+    pub x_max: u32,
+    /// Bottom-most pixel row (inclusive).
+    pub y_max: u32,
+    /// Number of visible pixels belonging to the object.
+    pub area: usize,
+    /// Rough occlusion estimate in `[0, 1]`: the fraction of the box not covered by
+    /// the object's visible pixels.
+    pub occlusion: f64,
+}
+
+impl BoundingBox2D {
+    pub fn width(&self) -> u32 {
+        self.x_max - self.x_min + 1
+    }
+
+    pub fn height(&self) -> u32 {
+        self.y_max - self.y_min + 1
+    }
+}
+
+/// Filters applied by [`extract_bounding_boxes`].
+#[derive(Debug, Clone, Default)]
+pub struct BoundingBoxFilter {
+    /// Minimum box width in pixels.
+    pub min_width: u32,
+    /// Minimum box height in pixels.
+    pub min_height: u32,
+    /// Minimum number of visible pixels.
+    pub min_area: usize,
+    /// Give each 4-connected region of an instance colour its own box, instead of one
+    /// box per instance colour. An object split by an occluder then yields one box per
+    /// visible part, each with an occlusion estimate of that part alone.
+    pub split_regions: bool,
+}
+
+/// Accumulated statistics for one object or region.
+struct Object {
+    colour: [u8; 3],
+    x_min: u32,
+    y_min: u32,
+    x_max: u32,
+    y_max: u32,
+    area: usize,
+    votes: BTreeMap<u16, usize>,
+}
+
+impl Object {
+    fn new(colour: [u8; 3]) -> Self {
+        Self {
+            colour,
+            x_min: u32::MAX,
+            y_min: u32::MAX,
+            x_max: 0,
+            y_max: 0,
+            area: 0,
+            votes: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, x: usize, y: usize, label: u16) {
+        self.x_min = self.x_min.min(x as u32);
+        self.y_min = self.y_min.min(y as u32);
+        self.x_max = self.x_max.max(x as u32);
+        self.y_max = self.y_max.max(y as u32);
+        self.area += 1;
+        *self.votes.entry(label).or_default() += 1;
+    }
+}
+
+/// Extract per-object 2D bounding boxes from an instance image.
+///
+/// `instance` is the raw RGBA instance buffer from a camera opened with
+/// `is_render_instance: true`, and `semantic` is the decoded annotation image of the
+/// same frame. As in BeamNGpy, pixels are grouped by instance colour and each colour
+/// becomes one box, so an object partly hidden by an occluder keeps a single box whose
+/// occlusion reflects the hidden part. Set [`BoundingBoxFilter::split_regions`] to get
+/// one box per 4-connected region instead. Pixels without a semantic class
+/// (background, sky, unknown colours) are ignored.
+pub fn extract_bounding_boxes(
+    instance: &[u8],
+    semantic: &SemanticImage,
+    filter: &BoundingBoxFilter,
+) -> Result<Vec<BoundingBox2D>> {
+    let (width, height) = (semantic.width as usize, semantic.height as usize);
+    let pixels = width * height;
+    if instance.len() < pixels * 4 {
+        return Err(BngError::ValueError(format!(
+            "Instance buffer has {} bytes, expected {} for {width}x{height}",
+            instance.len(),
+            pixels * 4
+        )));
+    }
+
+    let colour_at = |i: usize| [instance[i * 4], instance[i * 4 + 1], instance[i * 4 + 2]];
+    let labelled = |i: usize| semantic.labels[i] != SemanticImage::UNLABELLED;
+
+    let objects: Vec<Object> = if filter.split_regions {
+        connected_regions(width, height, &colour_at, &labelled, &semantic.labels)
+    } else {
+        let mut by_colour: BTreeMap<[u8; 3], Object> = BTreeMap::new();
+        for i in (0..pixels).filter(|&i| labelled(i)) {
+            let colour = colour_at(i);
+            by_colour
+                .entry(colour)
+                .or_insert_with(|| Object::new(colour))
+                .add(i % width, i / width, semantic.labels[i]);
+        }
+        by_colour.into_values().collect()
+    };
+
+    let mut boxes: Vec<BoundingBox2D> = objects
+        .into_iter()
+        .filter_map(|obj| {
+            let (&label, _) = obj
+                .votes
+                .iter()
+                .max_by_key(|(&label, &n)| (n, Reverse(label)))?;
+            let class = semantic.class_names.get(label as usize)?.clone();
+            let bbox = BoundingBox2D {
+                class,
+                instance_colour: obj.colour,
+                x_min: obj.x_min,
+                y_min: obj.y_min,
+                x_max: obj.x_max,
+                y_max: obj.y_max,
+                area: obj.area,
+                occlusion: 0.0,
+            };
+            let box_area = bbox.width() as f64 * bbox.height() as f64;
+            Some(BoundingBox2D {
+                occlusion: (1.0 - obj.area as f64 / box_area).clamp(0.0, 1.0),
+                ..bbox
+            })
+        })
+        .filter(|b| {
+            b.width() >= filter.min_width
+                && b.height() >= filter.min_height
+                && b.area >= filter.min_area
+        })
+        .collect();
+
+    boxes.sort_by_key(|b| (b.y_min, b.x_min, b.y_max, b.x_max));
+    Ok(boxes)
+}
+
+/// Segment the labelled pixels into 4-connected regions of equal instance colour.
+fn connected_regions(
+    width: usize,
+    height: usize,
+    colour_at: &impl Fn(usize) -> [u8; 3],
+    labelled: &impl Fn(usize) -> bool,
+    labels: &[u16],
+) -> Vec<Object> {
+    let mut visited = vec![false; width * height];
+    let mut objects = Vec::new();
+    let mut stack = Vec::new();
+
+    for start in 0..width * height {
+        if visited[start] || !labelled(start) {
+            continue;
+        }
+        let colour = colour_at(start);
+        let mut obj = Object::new(colour);
+
+        visited[start] = true;
+        stack.push(start);
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % width, i / width);
+            obj.add(x, y, labels[i]);
+
+            let mut visit = |j: usize| {
+                if !visited[j] && labelled(j) && colour_at(j) == colour {
+                    visited[j] = true;
+                    stack.push(j);
+                }
+            };
+            if x > 0 {
+                visit(i - 1);
+            }
+            if x + 1 < width {
+                visit(i + 1);
+            }
+            if y > 0 {
+                visit(i - width);
+            }
+            if y + 1 < height {
+                visit(i + width);
+            }
+        }
+        objects.push(obj);
+    }
+    objects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::AnnotationClasses;
+
+    fn rgba(pixels: &[[u8; 3]]) -> Vec<u8> {
+        pixels
+            .iter()
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect()
+    }
+
+    #[test]
+    fn test_extract_boxes_by_colour_and_region() {
+        const CAR: [u8; 3] = [255, 0, 0];
+        const ROAD: [u8; 3] = [0, 0, 255];
+        const SKY: [u8; 3] = [0, 0, 0];
+        const A: [u8; 3] = [10, 10, 10];
+        const B: [u8; 3] = [20, 20, 20];
+        let classes = AnnotationClasses::from_pairs([("CAR", CAR), ("ROAD", ROAD)]);
+
+        // 4x2 image: car A split by a pole in column 1, car B a single pixel.
+        let semantic = rgba(&[CAR, SKY, CAR, SKY, CAR, SKY, CAR, CAR]);
+        let instance = rgba(&[A, SKY, A, SKY, A, SKY, A, B]);
+        let semantic = SemanticImage::from_raw(&semantic, (4, 2), &classes).unwrap();
+        let regions = |boxes: &[BoundingBox2D]| -> Vec<_> {
+            boxes
+                .iter()
+                .map(|b| {
+                    (
+                        b.instance_colour,
+                        b.x_min,
+                        b.y_min,
+                        b.x_max,
+                        b.y_max,
+                        b.area,
+                    )
+                })
+                .collect()
+        };
+
+        // By default car A keeps one box, a third of which the pole hides.
+        let boxes =
+            extract_bounding_boxes(&instance, &semantic, &BoundingBoxFilter::default()).unwrap();
+        assert_eq!(
+            regions(&boxes),
+            vec![(A, 0, 0, 2, 1, 4), (B, 3, 1, 3, 1, 1)]
+        );
+        assert!(boxes.iter().all(|b| b.class == "CAR"));
+        assert!((boxes[0].occlusion - 1.0 / 3.0).abs() < 1e-9);
+
+        let split = BoundingBoxFilter {
+            split_regions: true,
+            ..Default::default()
+        };
+        let boxes = extract_bounding_boxes(&instance, &semantic, &split).unwrap();
+        assert_eq!(
+            regions(&boxes),
+            vec![(A, 0, 0, 0, 1, 2), (A, 2, 0, 2, 1, 2), (B, 3, 1, 3, 1, 1)]
+        );
+        assert!(boxes.iter().all(|b| b.occlusion == 0.0));
+
+        let filter = BoundingBoxFilter {
+            min_area: 2,
+            split_regions: true,
+            ..Default::default()
+        };
+        let boxes = extract_bounding_boxes(&instance, &semantic, &filter).unwrap();
+        assert_eq!(boxes.len(), 2);
+
+        // An even class vote goes to the class listed first in the semantic image.
+        for pixels in [[ROAD, CAR], [CAR, ROAD]] {
+            let semantic = SemanticImage::from_raw(&rgba(&pixels), (2, 1), &classes).unwrap();
+            let boxes = extract_bounding_boxes(&rgba(&[A, A]), &semantic, &filter).unwrap();
+            assert_eq!(boxes[0].class, semantic.class_names[0]);
+        }
+    }
+}
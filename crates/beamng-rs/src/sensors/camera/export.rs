@@ -0,0 +1,312 @@
+//! Image and calibration exports. PNG output needs the `png` feature, which is on
+//! by default; PFM, PPM and the calibration YAML are always available.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[cfg(feature = "png")]
+use beamng_proto::BngError;
+use beamng_proto::Result;
+
+#[cfg(feature = "png")]
+use super::images::{AnnotationClasses, SemanticImage};
+use super::images::{ColourImage, DepthImage};
+use super::projection::{CameraIntrinsics, CoordinateFrame};
+use super::Camera;
+
+fn create(path: impl AsRef<Path>) -> Result<BufWriter<File>> {
+    Ok(BufWriter::new(File::create(path)?))
+}
+
+#[cfg(feature = "png")]
+fn png_error(e: png::EncodingError) -> BngError {
+    BngError::Io(std::io::Error::other(format!("PNG encode: {e}")))
+}
+
+/// Encode one 8- or 16-bit PNG image.
+#[cfg(feature = "png")]
+fn write_png<W: Write>(
+    w: W,
+    width: u32,
+    height: u32,
+    colour: png::ColorType,
+    depth: png::BitDepth,
+    palette: Option<Vec<u8>>,
+    data: &[u8],
+) -> Result<()> {
+    let mut encoder = png::Encoder::new(w, width, height);
+    encoder.set_color(colour);
+    encoder.set_depth(depth);
+    if let Some(palette) = palette {
+        encoder.set_palette(palette);
+    }
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    writer.write_image_data(data).map_err(png_error)?;
+    writer.finish().map_err(png_error)
+}
+
+impl ColourImage {
+    /// Write the image as an 8-bit RGBA PNG.
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, w: W) -> Result<()> {
+        write_png(
+            w,
+            self.width,
+            self.height,
+            png::ColorType::Rgba,
+            png::BitDepth::Eight,
+            None,
+            &self.data,
+        )
+    }
+
+    /// Save the image as an 8-bit RGBA PNG at `path`.
+    #[cfg(feature = "png")]
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.write_png(create(path)?)
+    }
+
+    /// Write the image as a binary (P6) PPM, dropping alpha.
+    pub fn write_ppm<W: Write>(&self, mut w: W) -> Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        w.write_all(&self.to_rgb())?;
+        w.flush()?;
+        Ok(())
+    }
+
+    pub fn save_ppm(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.write_ppm(create(path)?)
+    }
+}
+
+impl DepthImage {
+    /// Write the image as a 16-bit greyscale PNG in millimetres.
+    ///
+    /// Depths beyond 65.535 m saturate; non-finite and negative depths are written as 0.
+    #[cfg(feature = "png")]
+    pub fn write_png_mm<W: Write>(&self, w: W) -> Result<()> {
+        let data: Vec<u8> = self
+            .data
+            .iter()
+            .map(|&d| {
+                if d.is_finite() && d > 0.0 {
+                    (d as f64 * 1000.0).round().min(u16::MAX as f64) as u16
+                } else {
+                    0
+                }
+            })
+            .flat_map(u16::to_be_bytes)
+            .collect();
+        write_png(
+            w,
+            self.width,
+            self.height,
+            png::ColorType::Grayscale,
+            png::BitDepth::Sixteen,
+            None,
+            &data,
+        )
+    }
+
+    /// Save the image as a 16-bit greyscale PNG in millimetres at `path`.
+    #[cfg(feature = "png")]
+    pub fn save_png_mm(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.write_png_mm(create(path)?)
+    }
+
+    /// Write the image as a single-channel little-endian PFM in metres.
+    ///
+    /// PFM stores rows bottom-to-top, so rows are flipped on write.
+    pub fn write_pfm<W: Write>(&self, mut w: W) -> Result<()> {
+        write!(w, "Pf\n{} {}\n-1.0\n", self.width, self.height)?;
+        let width = self.width as usize;
+        if width > 0 {
+            for row in self.data.chunks_exact(width).rev() {
+                for d in row {
+                    w.write_all(&d.to_le_bytes())?;
+                }
+            }
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    pub fn save_pfm(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.write_pfm(create(path)?)
+    }
+}
+
+#[cfg(feature = "png")]
+impl SemanticImage {
+    /// Write the image as an 8-bit indexed PNG whose palette holds the annotation colours.
+    ///
+    /// Palette index 0 is black and used for unlabelled pixels; the classes of
+    /// `classes` follow in name order, so indices are stable across frames.
+    pub fn write_indexed_png<W: Write>(&self, w: W, classes: &AnnotationClasses) -> Result<()> {
+        let mut entries: Vec<([u8; 3], &str)> = classes.iter().collect();
+        if entries.len() > 255 {
+            return Err(BngError::ValueError(format!(
+                "Indexed PNG holds at most 255 classes, got {}",
+                entries.len()
+            )));
+        }
+        entries.sort_by_key(|(_, name)| *name);
+
+        let mut palette = vec![0, 0, 0];
+        palette.extend(entries.iter().flat_map(|(c, _)| *c));
+        let index_of_label: Vec<u8> = self
+            .class_names
+            .iter()
+            .map(|name| {
+                entries
+                    .iter()
+                    .position(|(_, n)| n == name)
+                    .map_or(0, |i| i as u8 + 1)
+            })
+            .collect();
+        let data: Vec<u8> = self
+            .labels
+            .iter()
+            .map(|&l| index_of_label.get(l as usize).copied().unwrap_or(0))
+            .collect();
+
+        write_png(
+            w,
+            self.width,
+            self.height,
+            png::ColorType::Indexed,
+            png::BitDepth::Eight,
+            Some(palette),
+            &data,
+        )
+    }
+
+    pub fn save_indexed_png(
+        &self,
+        path: impl AsRef<Path>,
+        classes: &AnnotationClasses,
+    ) -> Result<()> {
+        self.write_indexed_png(create(path)?, classes)
+    }
+}
+
+/// Write one `!!opencv-matrix` entry of an OpenCV YAML file.
+fn write_matrix<W: Write>(
+    w: &mut W,
+    name: &str,
+    rows: usize,
+    cols: usize,
+    data: &[f64],
+) -> Result<()> {
+    let values: Vec<String> = data.iter().map(|v| format!("{v:?}")).collect();
+    write!(
+        w,
+        "{name}: !!opencv-matrix\n   rows: {rows}\n   cols: {cols}\n   dt: d\n   data: [ {} ]\n",
+        values.join(", ")
+    )?;
+    Ok(())
+}
+
+/// Write a camera calibration in OpenCV YAML format, readable with `cv::FileStorage`.
+///
+/// The extrinsics are written OpenCV-style as `rotation_matrix` and
+/// `translation_vector`, mapping a point `p` in the parent frame to the camera frame
+/// as `R * p + t`. Distortion coefficients are all zero, since the simulated camera is
+/// an ideal pinhole.
+pub fn write_calibration_yaml<W: Write>(
+    mut w: W,
+    intrinsics: &CameraIntrinsics,
+    extrinsics: &CoordinateFrame,
+) -> Result<()> {
+    let (x, y, z) = (extrinsics.x, extrinsics.y, extrinsics.z);
+    let rotation = [x.0, x.1, x.2, y.0, y.1, y.2, z.0, z.1, z.2];
+    let t = extrinsics.from_parent((0.0, 0.0, 0.0));
+
+    write!(
+        w,
+        "%YAML:1.0\n---\nimage_width: {}\nimage_height: {}\n",
+        intrinsics.width, intrinsics.height
+    )?;
+    write_matrix(&mut w, "camera_matrix", 3, 3, &intrinsics.matrix())?;
+    write_matrix(&mut w, "distortion_coefficients", 1, 5, &[0.0; 5])?;
+    write_matrix(&mut w, "rotation_matrix", 3, 3, &rotation)?;
+    write_matrix(&mut w, "translation_vector", 3, 1, &[t.0, t.1, t.2])?;
+    w.flush()?;
+    Ok(())
+}
+
+impl Camera {
+    /// Write this camera's intrinsics and extrinsics (relative to the vehicle, or the
+    /// world for unattached cameras) as an OpenCV YAML calibration file.
+    pub fn save_calibration(&self, path: impl AsRef<Path>) -> Result<()> {
+        write_calibration_yaml(create(path)?, &self.intrinsics(), &self.extrinsics())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::camera::images::PixelLayout;
+
+    #[test]
+    fn test_pfm_and_ppm_exports() {
+        let depth = DepthImage {
+            width: 2,
+            height: 2,
+            data: vec![1.5, f32::NAN, 100.0, 0.25],
+        };
+        let mut out = Vec::new();
+        depth.write_pfm(&mut out).unwrap();
+        let header = b"Pf\n2 2\n-1.0\n";
+        assert_eq!(&out[..header.len()], header);
+        // Bottom row first.
+        let first = f32::from_le_bytes(out[header.len()..header.len() + 4].try_into().unwrap());
+        assert_eq!(first, 100.0);
+
+        let raw = [0, 0, 255, 255, 1, 2, 3, 255, 255, 0, 0, 255];
+        let colour = ColourImage::from_raw(&raw, (3, 1), PixelLayout::Rgba8).unwrap();
+        let mut out = Vec::new();
+        colour.write_ppm(&mut out).unwrap();
+        assert_eq!(&out[..11], b"P6\n3 1\n255\n");
+        assert_eq!(out.len(), 11 + 9);
+    }
+
+    #[cfg(feature = "png")]
+    fn decode(bytes: &[u8]) -> (png::OutputInfo, Vec<u8>, Option<Vec<u8>>) {
+        let mut reader = png::Decoder::new(bytes).read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        let palette = reader.info().palette.as_ref().map(|p| p.to_vec());
+        buf.truncate(info.buffer_size());
+        (info, buf, palette)
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_png_exports() {
+        let depth = DepthImage {
+            width: 2,
+            height: 2,
+            data: vec![1.5, f32::NAN, 100.0, 0.25],
+        };
+        let mut out = Vec::new();
+        depth.write_png_mm(&mut out).unwrap();
+        let (info, buf, _) = decode(&out);
+        assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+        let mm: Vec<u16> = buf
+            .chunks_exact(2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .collect();
+        assert_eq!(mm, vec![1500, 0, u16::MAX, 250]);
+
+        let classes = AnnotationClasses::from_pairs([("ROAD", [0, 0, 255]), ("CAR", [255, 0, 0])]);
+        let raw = [0, 0, 255, 255, 1, 2, 3, 255, 255, 0, 0, 255];
+        let semantic = SemanticImage::from_raw(&raw, (3, 1), &classes).unwrap();
+        let mut out = Vec::new();
+        semantic.write_indexed_png(&mut out, &classes).unwrap();
+        let (_, buf, palette) = decode(&out);
+        assert_eq!(palette.unwrap(), vec![0, 0, 0, 255, 0, 0, 0, 0, 255]);
+        assert_eq!(buf, vec![2, 0, 1]);
+    }
+}
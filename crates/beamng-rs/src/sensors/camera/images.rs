@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+
+use beamng_proto::types::{value_to_str_dict, Float2, Int2, StrDict};
+use beamng_proto::{BngError, Result};
+
+/// Check that a raw buffer holds at least `width * height * bytes_per_pixel` bytes.
+fn check_len(what: &str, len: usize, resolution: Int2, bytes_per_pixel: usize) -> Result<()> {
+    let expected = resolution.0 as usize * resolution.1 as usize * bytes_per_pixel;
+    if len < expected {
+        return Err(BngError::ValueError(format!(
+            "{what} buffer has {len} bytes, expected {expected} for {}x{}",
+            resolution.0, resolution.1
+        )));
+    }
+    Ok(())
+}
+
+/// How the pixels of a raw camera buffer are laid out.
+///
+/// Shared-memory buffers always hold `width * height * 4` bytes whatever their content,
+/// while network buffers are packed, so the layout cannot be told from the buffer length.
+/// [`Camera::decode`](super::Camera::decode) picks it from the reading's
+/// [`BufferSource`] and the camera configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelLayout {
+    /// Four bytes per pixel, RGBA.
+    Rgba8,
+    /// Three bytes per pixel, RGB.
+    Rgb8,
+    /// One byte per pixel.
+    Luma8,
+    /// One little-endian `f32` per pixel.
+    F32,
+}
+
+impl PixelLayout {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelLayout::Rgba8 | PixelLayout::F32 => 4,
+            PixelLayout::Rgb8 => 3,
+            PixelLayout::Luma8 => 1,
+        }
+    }
+}
+
+/// Where the bytes of a [`CameraRawReadings`](super::CameraRawReadings) came from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BufferSource {
+    /// Read from the camera's shared-memory segments.
+    SharedMemory,
+    /// Carried in a network response.
+    #[default]
+    Network,
+}
+
+/// An 8-bit RGBA colour image, stored row-major from the top-left pixel.
+#[derive(Debug, Clone)]
+pub struct ColourImage {
+    pub width: u32,
+    pub height: u32,
+    /// `width * height * 4` bytes of RGBA data.
+    pub data: Vec<u8>,
+}
+
+impl ColourImage {
+    /// Decode a raw colour buffer of the given resolution and layout, which must be
+    /// [`Rgba8`](PixelLayout::Rgba8) or [`Rgb8`](PixelLayout::Rgb8).
+    ///
+    /// The alpha channel is forced to 255, since the simulator does not fill it.
+    pub fn from_raw(raw: &[u8], resolution: Int2, layout: PixelLayout) -> Result<Self> {
+        let (width, height) = resolution;
+        let pixels = width as usize * height as usize;
+        check_len("Colour", raw.len(), resolution, layout.bytes_per_pixel())?;
+        let data = match layout {
+            PixelLayout::Rgba8 => {
+                let mut data = raw[..pixels * 4].to_vec();
+                for a in data.iter_mut().skip(3).step_by(4) {
+                    *a = 255;
+                }
+                data
+            }
+            PixelLayout::Rgb8 => raw[..pixels * 3]
+                .chunks_exact(3)
+                .flat_map(|p| [p[0], p[1], p[2], 255])
+                .collect(),
+            PixelLayout::Luma8 | PixelLayout::F32 => {
+                return Err(BngError::ValueError(format!(
+                    "Colour buffers cannot have layout {layout:?}"
+                )))
+            }
+        };
+        Ok(Self {
+            width,
+            height,
+            data,
+        })
+    }
+
+    /// Get the RGBA value of a pixel, or `None` if out of bounds.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        Some([
+            self.data[i],
+            self.data[i + 1],
+            self.data[i + 2],
+            self.data[i + 3],
+        ])
+    }
+
+    /// Return the image as packed RGB bytes (alpha dropped).
+    pub fn to_rgb(&self) -> Vec<u8> {
+        self.data
+            .chunks_exact(4)
+            .flat_map(|p| [p[0], p[1], p[2]])
+            .collect()
+    }
+}
+
+/// A depth image with per-pixel distances in metres.
+#[derive(Debug, Clone)]
+pub struct DepthImage {
+    pub width: u32,
+    pub height: u32,
+    /// `width * height` depth values in metres.
+    pub data: Vec<f32>,
+}
+
+impl DepthImage {
+    /// Decode a raw depth buffer of the given resolution and layout.
+    ///
+    /// [`F32`](PixelLayout::F32) pixels are distances in metres, clamped to
+    /// `near_far_planes` so that sky and other pixels beyond the far plane read as the
+    /// far plane. [`Luma8`](PixelLayout::Luma8) pixels, and the first channel of
+    /// [`Rgba8`](PixelLayout::Rgba8) pixels, map `0..=255` linearly onto `near_far_planes`.
+    pub fn from_raw(
+        raw: &[u8],
+        resolution: Int2,
+        near_far_planes: Float2,
+        layout: PixelLayout,
+    ) -> Result<Self> {
+        let (width, height) = resolution;
+        let pixels = width as usize * height as usize;
+        let stride = layout.bytes_per_pixel();
+        check_len("Depth", raw.len(), resolution, stride)?;
+        let (near, far) = near_far_planes;
+        let data = match layout {
+            PixelLayout::F32 => raw[..pixels * 4]
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .map(|d| (d as f64).clamp(near, far) as f32)
+                .collect(),
+            PixelLayout::Luma8 | PixelLayout::Rgba8 => raw
+                .iter()
+                .step_by(stride)
+                .take(pixels)
+                .map(|&v| (near + (v as f64 / 255.0) * (far - near)) as f32)
+                .collect(),
+            PixelLayout::Rgb8 => {
+                return Err(BngError::ValueError(
+                    "Depth buffers cannot have layout Rgb8".into(),
+                ))
+            }
+        };
+        Ok(Self {
+            width,
+            height,
+            data,
+        })
+    }
+
+    /// Get the depth of a pixel in metres, or `None` if out of bounds.
+    pub fn get(&self, x: u32, y: u32) -> Option<f32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.data[y as usize * self.width as usize + x as usize])
+    }
+}
+
+/// The annotation colour table, mapping semantic class names to RGB colours.
+///
+/// Built from the response of [`CameraApi::get_annotations`](crate::api::beamng::CameraApi::get_annotations).
+#[derive(Debug, Clone, Default)]
+pub struct AnnotationClasses {
+    by_colour: HashMap<[u8; 3], String>,
+}
+
+impl AnnotationClasses {
+    /// Parse a `GetAnnotations` response (`{"annotations": {class: [r, g, b]}}`).
+    pub fn from_response(resp: &StrDict) -> Self {
+        let mut by_colour = HashMap::new();
+        let table = resp
+            .get("annotations")
+            .cloned()
+            .and_then(value_to_str_dict)
+            .unwrap_or_default();
+        for (name, colour) in table {
+            let Some(arr) = colour.as_array() else {
+                continue;
+            };
+            let channels: Vec<u8> = arr
+                .iter()
+                .filter_map(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64)))
+                .map(|v| v.min(255) as u8)
+                .collect();
+            if channels.len() >= 3 {
+                by_colour.insert([channels[0], channels[1], channels[2]], name);
+            }
+        }
+        Self { by_colour }
+    }
+
+    /// Build a table from `(class, colour)` pairs.
+    pub fn from_pairs<I, S>(pairs: I) -> Self
+    where
+        I: IntoIterator<Item = (S, [u8; 3])>,
+        S: Into<String>,
+    {
+        Self {
+            by_colour: pairs.into_iter().map(|(n, c)| (c, n.into())).collect(),
+        }
+    }
+
+    /// Look up the class name for an annotation colour.
+    pub fn class_of(&self, colour: [u8; 3]) -> Option<&str> {
+        self.by_colour.get(&colour).map(|s| s.as_str())
+    }
+
+    /// Look up the annotation colour of a class.
+    pub fn colour_of(&self, class: &str) -> Option<[u8; 3]> {
+        self.by_colour
+            .iter()
+            .find(|(_, n)| n.as_str() == class)
+            .map(|(c, _)| *c)
+    }
+
+    /// Iterate over `(colour, class)` pairs.
+    pub fn iter(&self) -> impl Iterator<Item = ([u8; 3], &str)> {
+        self.by_colour.iter().map(|(c, n)| (*c, n.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_colour.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_colour.is_empty()
+    }
+}
+
+/// A semantic segmentation image: each pixel holds an index into [`class_names`](Self::class_names).
+#[derive(Debug, Clone)]
+pub struct SemanticImage {
+    pub width: u32,
+    pub height: u32,
+    /// The class names referenced by [`labels`](Self::labels).
+    pub class_names: Vec<String>,
+    /// `width * height` class indices; [`UNLABELLED`](Self::UNLABELLED) for unknown colours.
+    pub labels: Vec<u16>,
+}
+
+impl SemanticImage {
+    /// Label value for pixels whose colour is not in the annotation table.
+    pub const UNLABELLED: u16 = u16::MAX;
+
+    /// Decode a raw RGBA annotation buffer using the given colour table.
+    pub fn from_raw(raw: &[u8], resolution: Int2, classes: &AnnotationClasses) -> Result<Self> {
+        check_len("Annotation", raw.len(), resolution, 4)?;
+        let (width, height) = resolution;
+        let pixels = width as usize * height as usize;
+
+        let mut class_names: Vec<String> = Vec::new();
+        let mut index_of: HashMap<[u8; 3], u16> = HashMap::new();
+        let labels = raw[..pixels * 4]
+            .chunks_exact(4)
+            .map(|p| {
+                let colour = [p[0], p[1], p[2]];
+                if let Some(&i) = index_of.get(&colour) {
+                    return i;
+                }
+                let Some(name) = classes.class_of(colour) else {
+                    return Self::UNLABELLED;
+                };
+                let i = class_names.len() as u16;
+                class_names.push(name.to_string());
+                index_of.insert(colour, i);
+                i
+            })
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            class_names,
+            labels,
+        })
+    }
+
+    /// Get the class name of a pixel, or `None` if out of bounds or unlabelled.
+    pub fn class_at(&self, x: u32, y: u32) -> Option<&str> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let label = self.labels[y as usize * self.width as usize + x as usize];
+        self.class_names.get(label as usize).map(|s| s.as_str())
+    }
+}
+
+/// Decoded images from a single camera reading.
+#[derive(Debug, Clone, Default)]
+pub struct CameraImages {
+    pub colour: Option<ColourImage>,
+    pub depth: Option<DepthImage>,
+    /// Only present when an [`AnnotationClasses`] table was supplied.
+    pub semantic: Option<SemanticImage>,
+}
+
+#[cfg(feature = "image")]
+impl TryFrom<ColourImage> for image::RgbaImage {
+    type Error = BngError;
+
+    fn try_from(img: ColourImage) -> Result<Self> {
+        image::RgbaImage::from_raw(img.width, img.height, img.data).ok_or_else(|| {
+            BngError::ValueError("ColourImage data does not match its dimensions".into())
+        })
+    }
+}
+
+#[cfg(feature = "image")]
+impl TryFrom<DepthImage> for image::ImageBuffer<image::Luma<f32>, Vec<f32>> {
+    type Error = BngError;
+
+    fn try_from(img: DepthImage) -> Result<Self> {
+        image::ImageBuffer::from_raw(img.width, img.height, img.data).ok_or_else(|| {
+            BngError::ValueError("DepthImage data does not match its dimensions".into())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_float_and_integer() {
+        let raw: Vec<u8> = [1.5f32, 20.0]
+            .iter()
+            .flat_map(|f| f.to_le_bytes())
+            .collect();
+        let img = DepthImage::from_raw(&raw, (2, 1), (0.1, 100.0), PixelLayout::F32).unwrap();
+        assert_eq!(img.data, vec![1.5, 20.0]);
+        let img = DepthImage::from_raw(&raw, (2, 1), (2.0, 10.0), PixelLayout::F32).unwrap();
+        assert_eq!(img.data, vec![2.0, 10.0]);
+
+        let img =
+            DepthImage::from_raw(&[0, 255], (2, 1), (10.0, 20.0), PixelLayout::Luma8).unwrap();
+        assert_eq!(img.data, vec![10.0, 20.0]);
+        // A shared-memory buffer is RGBA-sized even for integer depth.
+        let shmem = [255, 0, 0, 0, 0, 9, 9, 9];
+        let img = DepthImage::from_raw(&shmem, (2, 1), (10.0, 20.0), PixelLayout::Rgba8).unwrap();
+        assert_eq!(img.data, vec![20.0, 10.0]);
+
+        assert!(DepthImage::from_raw(&[0; 4], (2, 1), (0.1, 100.0), PixelLayout::F32).is_err());
+        // Single-channel data is not misread as RGB just because it is short.
+        assert!(ColourImage::from_raw(&[0; 6], (2, 1), PixelLayout::Rgba8).is_err());
+    }
+
+    #[test]
+    fn test_semantic_lookup() {
+        let classes = AnnotationClasses::from_pairs([("CAR", [255, 0, 0]), ("ROAD", [0, 0, 255])]);
+        let raw = [255, 0, 0, 255, 1, 2, 3, 255, 0, 0, 255, 255];
+        let img = SemanticImage::from_raw(&raw, (3, 1), &classes).unwrap();
+        assert_eq!(img.class_at(0, 0), Some("CAR"));
+        assert_eq!(img.class_at(1, 0), None);
+        assert_eq!(img.class_at(2, 0), Some("ROAD"));
+        assert_eq!(img.labels[1], SemanticImage::UNLABELLED);
+    }
+}
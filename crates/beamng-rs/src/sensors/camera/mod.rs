@@ -1,15 +1,39 @@
-use beamng_proto::types::{Float2, Int2, Vec3};
+use beamng_proto::types::{Float2, Int2, StrDict, Vec3};
 use beamng_proto::{BngError, Result};
+use serde::{Deserialize, Serialize};
 use shared_memory::{Shmem, ShmemConf};
 use tracing::info;
 
 use crate::beamng::BeamNg;
+use crate::sensors::registry::{mark_dropped, SharedSensorRegistry};
+use crate::sensors::{GeSensor, SensorKind};
 use crate::vehicle::Vehicle;
 
+mod bbox;
+mod export;
+mod images;
+mod params;
+mod projection;
+mod rig;
+mod stream;
+mod view;
+
+pub use bbox::{extract_bounding_boxes, BoundingBox2D, BoundingBoxFilter};
+pub use export::write_calibration_yaml;
+pub use images::{
+    AnnotationClasses, BufferSource, CameraImages, ColourImage, DepthImage, PixelLayout,
+    SemanticImage,
+};
+pub use projection::{CameraIntrinsics, CoordinateFrame, PointFrame, VehiclePose};
+pub use rig::{CameraRig, RigCapture, RigTrigger};
+pub use stream::{CameraFrame, FrameStreamOptions};
+pub use view::{CameraRawView, ShmemView};
+
 /// Configuration for a [`Camera`] sensor.
 ///
 /// All fields have defaults matching the Python SDK.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct CameraConfig {
     pub requested_update_time: f64,
     pub update_priority: f64,
@@ -61,11 +85,13 @@ impl Default for CameraConfig {
 }
 
 /// Raw image data from a camera reading.
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct CameraRawReadings {
     pub colour: Option<Vec<u8>>,
     pub annotation: Option<Vec<u8>>,
     pub depth: Option<Vec<u8>>,
+    /// Where the buffers were read from, which decides their [`PixelLayout`].
+    pub source: BufferSource,
 }
 
 /// Wraps an OS shared memory segment.
@@ -74,6 +100,13 @@ struct ShmemBuffer {
     size: usize,
 }
 
+// SAFETY: `Shmem` is not `Send`/`Sync` only because it holds the raw pointer to its
+// mapping. The mapping is valid in every thread of the process until the `Shmem` is
+// dropped, and it is only read through `&self`, with the same volatile copies used
+// against the simulator's concurrent writes.
+unsafe impl Send for ShmemBuffer {}
+unsafe impl Sync for ShmemBuffer {}
+
 impl ShmemBuffer {
     fn create(size: usize) -> Result<Self> {
         let shmem = ShmemConf::new().size(size).create().map_err(|e| {
@@ -87,12 +120,54 @@ impl ShmemBuffer {
     }
 
     fn read(&self) -> Vec<u8> {
-        let ptr = self.shmem.as_ptr();
-        let slice = unsafe { std::slice::from_raw_parts(ptr, self.size) };
-        slice.to_vec()
+        let mut buf = Vec::with_capacity(self.size);
+        self.read_into(&mut buf);
+        buf
+    }
+
+    /// Copy the segment into `buf`, reusing its allocation.
+    fn read_into(&self, buf: &mut Vec<u8>) {
+        // SAFETY: the segment is `size` bytes long and mapped for as long as `self` lives.
+        unsafe { copy_shared(self.shmem.as_ptr(), self.size, buf) }
+    }
+
+    /// Borrow the segment without copying.
+    ///
+    /// # Safety
+    /// The simulator must not write to the segment while the slice is alive.
+    unsafe fn as_slice(&self) -> &[u8] {
+        std::slice::from_raw_parts(self.shmem.as_ptr(), self.size)
     }
 }
 
+/// Copy `len` bytes from memory that another process may be writing concurrently.
+///
+/// The bytes are read with volatile reads from the raw pointer, never through a
+/// `&[u8]`, so the compiler cannot assume they stay unchanged during the copy. A
+/// concurrent write can still tear the copy, leaving it with parts of two frames.
+///
+/// # Safety
+/// `src` must be valid for reads of `len` bytes.
+unsafe fn copy_shared(src: *const u8, len: usize, buf: &mut Vec<u8>) {
+    const WORD: usize = std::mem::size_of::<u64>();
+    buf.clear();
+    buf.reserve(len);
+    let dst = buf.as_mut_ptr();
+    let mut i = 0;
+    if src.align_offset(WORD) == 0 {
+        while i + WORD <= len {
+            let word = std::ptr::read_volatile(src.add(i) as *const u64);
+            std::ptr::write_unaligned(dst.add(i) as *mut u64, word);
+            i += WORD;
+        }
+    }
+    while i < len {
+        *dst.add(i) = std::ptr::read_volatile(src.add(i));
+        i += 1;
+    }
+    buf.set_len(len);
+}
+
 /// Extract raw bytes from a msgpack value (handles both Binary and String).
 fn value_to_bytes(val: &rmpv::Value) -> Option<Vec<u8>> {
     match val {
@@ -102,6 +177,27 @@ fn value_to_bytes(val: &rmpv::Value) -> Option<Vec<u8>> {
     }
 }
 
+/// Build readings from a response carrying the images in its `data` map:
+/// `{ "data": { "colour": <bytes>, "annotation": <bytes>, "depth": <bytes> } }`.
+fn readings_from_data(resp: &StrDict) -> CameraRawReadings {
+    let data = resp
+        .get("data")
+        .cloned()
+        .and_then(beamng_proto::types::value_to_str_dict);
+    let get = |key: &str| {
+        data.as_ref()
+            .and_then(|d| d.get(key))
+            .and_then(value_to_bytes)
+    };
+
+    CameraRawReadings {
+        colour: get("colour"),
+        annotation: get("annotation"),
+        depth: get("depth"),
+        source: BufferSource::Network,
+    }
+}
+
 /// A camera sensor attached to the simulator (GE-level), optionally tracking a vehicle.
 ///
 /// Uses shared memory for high-performance image streaming. The camera communicates
@@ -127,10 +223,18 @@ fn value_to_bytes(val: &rmpv::Value) -> Option<Vec<u8>> {
 /// ```
 pub struct Camera {
     name: String,
+    vid: Option<String>,
     config: CameraConfig,
     colour_shmem: Option<ShmemBuffer>,
     annotation_shmem: Option<ShmemBuffer>,
     depth_shmem: Option<ShmemBuffer>,
+    registry: SharedSensorRegistry,
+}
+
+impl Drop for Camera {
+    fn drop(&mut self) {
+        mark_dropped(&self.registry, &self.name);
+    }
 }
 
 impl Camera {
@@ -144,6 +248,7 @@ impl Camera {
         config: CameraConfig,
     ) -> Result<Camera> {
         let name = name.into();
+        bng.check_sensor_name(&name)?;
         let buf_size = (config.resolution.0 * config.resolution.1 * 4) as usize;
 
         let colour_shmem = if config.is_using_shared_memory && config.is_render_colours {
@@ -285,13 +390,16 @@ impl Camera {
             .await?;
 
         info!("Opened Camera: \"{}\"", name);
+        bng.register_sensor(SensorKind::Camera, &name, vehicle.map(|v| v.vid.as_str()));
 
         Ok(Camera {
             name,
+            vid: vehicle.map(|v| v.vid.clone()),
             config,
             colour_shmem,
             annotation_shmem,
             depth_shmem,
+            registry: bng.shared_sensor_registry(),
         })
     }
 
@@ -300,6 +408,18 @@ impl Camera {
     /// This is the fastest path — no network round-trip. Requires the camera to have been
     /// created with `is_streaming: true` and `is_using_shared_memory: true`.
     pub fn stream_raw(&self) -> Result<CameraRawReadings> {
+        self.check_streaming()?;
+
+        Ok(CameraRawReadings {
+            colour: self.colour_shmem.as_ref().map(|s| s.read()),
+            annotation: self.annotation_shmem.as_ref().map(|s| s.read()),
+            depth: self.depth_shmem.as_ref().map(|s| s.read()),
+            source: BufferSource::SharedMemory,
+        })
+    }
+
+    /// Ensure the camera can be read directly from shared memory.
+    fn check_streaming(&self) -> Result<()> {
         if !self.config.is_streaming {
             return Err(BngError::ValueError(
                 "This camera was not created with is_streaming=true. Stream not available.".into(),
@@ -310,12 +430,7 @@ impl Camera {
                 "This camera was not created with is_using_shared_memory=true.".into(),
             ));
         }
-
-        Ok(CameraRawReadings {
-            colour: self.colour_shmem.as_ref().map(|s| s.read()),
-            annotation: self.annotation_shmem.as_ref().map(|s| s.read()),
-            depth: self.depth_shmem.as_ref().map(|s| s.read()),
-        })
+        Ok(())
     }
 
     /// Poll the simulator for the latest camera reading.
@@ -324,52 +439,41 @@ impl Camera {
     /// the local shared memory buffers. When shared memory is disabled, the image data
     /// is returned directly in the network response (required for remote connections).
     pub async fn poll_raw(&self, bng: &mut BeamNg) -> Result<CameraRawReadings> {
-        let conn = bng.conn()?;
-        let resp = conn
-            .request(
-                "PollCamera",
-                &[
-                    ("name", rmpv::Value::from(self.name.as_str())),
-                    (
-                        "isUsingSharedMemory",
-                        rmpv::Value::from(self.config.is_using_shared_memory),
-                    ),
-                ],
-            )
-            .await?;
+        let resp = self.send_poll_request(bng).await?;
+        Ok(self.readings_from_poll(&resp))
+    }
+
+    /// Fields of a `PollCamera` request for this camera.
+    fn poll_fields(&self) -> Vec<(&str, rmpv::Value)> {
+        vec![
+            ("name", rmpv::Value::from(self.name.as_str())),
+            (
+                "isUsingSharedMemory",
+                rmpv::Value::from(self.config.is_using_shared_memory),
+            ),
+        ]
+    }
+
+    /// Send a `PollCamera` request, filling the shared memory buffers if in use.
+    async fn send_poll_request(&self, bng: &mut BeamNg) -> Result<StrDict> {
+        bng.conn()?.request("PollCamera", &self.poll_fields()).await
+    }
 
+    /// Build readings from a `PollCamera` response, reading shared memory if in use.
+    fn readings_from_poll(&self, resp: &StrDict) -> CameraRawReadings {
         if self.config.is_using_shared_memory {
-            Ok(CameraRawReadings {
+            CameraRawReadings {
                 colour: self.colour_shmem.as_ref().map(|s| s.read()),
                 annotation: self.annotation_shmem.as_ref().map(|s| s.read()),
                 depth: self.depth_shmem.as_ref().map(|s| s.read()),
-            })
+                source: BufferSource::SharedMemory,
+            }
         } else {
-            // Response: { "data": { "colour": <bytes>, "annotation": <bytes>, "depth": <bytes> } }
-            let data = resp
-                .get("data")
-                .cloned()
-                .and_then(beamng_proto::types::value_to_str_dict);
-
-            if data.is_none() {
+            if !resp.contains_key("data") {
                 let keys: Vec<_> = resp.keys().collect();
                 info!("PollCamera: no 'data' map in response. Keys: {keys:?}");
             }
-
-            Ok(CameraRawReadings {
-                colour: data
-                    .as_ref()
-                    .and_then(|d| d.get("colour"))
-                    .and_then(value_to_bytes),
-                annotation: data
-                    .as_ref()
-                    .and_then(|d| d.get("annotation"))
-                    .and_then(value_to_bytes),
-                depth: data
-                    .as_ref()
-                    .and_then(|d| d.get("depth"))
-                    .and_then(value_to_bytes),
-            })
+            readings_from_data(resp)
         }
     }
 
@@ -379,78 +483,96 @@ impl Camera {
     /// a fresh render on the simulator side and waits for it to complete.
     /// Works over the network without shared memory.
     pub async fn ad_hoc_poll_raw(&self, bng: &mut BeamNg) -> Result<CameraRawReadings> {
-        let conn = bng.conn()?;
+        let request_id = self.send_ad_hoc_request(bng).await?;
+        self.collect_ad_hoc_request(bng, request_id).await
+    }
 
-        // 1. Request a render
-        let resp = conn
+    /// Request an ad-hoc render without waiting for it, returning the request ID.
+    pub async fn send_ad_hoc_request(&self, bng: &mut BeamNg) -> Result<u64> {
+        let resp = bng
+            .conn()?
             .request(
                 "SendAdHocRequestCamera",
                 &[("name", rmpv::Value::from(self.name.as_str()))],
             )
             .await?;
-        let request_id = resp
-            .get("data")
-            .and_then(|v| beamng_proto::types::value_as_u64(v))
-            .ok_or_else(|| BngError::ValueError("Missing request_id from ad-hoc poll".into()))?;
-
-        // 2. Wait until the render is ready
-        loop {
-            let conn = bng.conn()?;
-            let resp = conn
-                .request(
-                    "IsAdHocPollRequestReadyCamera",
-                    &[("requestId", rmpv::Value::from(request_id))],
-                )
-                .await?;
-            let ready = resp.get("data").and_then(|v| v.as_bool()).unwrap_or(false);
-            if ready {
-                break;
-            }
+        resp.get("data")
+            .and_then(beamng_proto::types::value_as_u64)
+            .ok_or_else(|| BngError::ValueError("Missing request_id from ad-hoc poll".into()))
+    }
+
+    /// Check whether an ad-hoc render request has completed.
+    pub async fn is_ad_hoc_request_ready(&self, bng: &mut BeamNg, request_id: u64) -> Result<bool> {
+        let resp = bng
+            .conn()?
+            .request(
+                "IsAdHocPollRequestReadyCamera",
+                &[("requestId", rmpv::Value::from(request_id))],
+            )
+            .await?;
+        Ok(resp.get("data").and_then(|v| v.as_bool()).unwrap_or(false))
+    }
+
+    /// Wait for an ad-hoc render request to complete and collect its data.
+    pub async fn collect_ad_hoc_request(
+        &self,
+        bng: &mut BeamNg,
+        request_id: u64,
+    ) -> Result<CameraRawReadings> {
+        while !self.is_ad_hoc_request_ready(bng, request_id).await? {
             tokio::time::sleep(std::time::Duration::from_millis(1)).await;
         }
 
-        // 3. Collect the rendered data
-        let conn = bng.conn()?;
-        let resp = conn
+        let resp = bng
+            .conn()?
             .request(
                 "CollectAdHocPollRequestCamera",
                 &[("requestId", rmpv::Value::from(request_id))],
             )
             .await?;
+        Ok(readings_from_data(&resp))
+    }
 
-        let data = resp
-            .get("data")
-            .cloned()
-            .and_then(beamng_proto::types::value_to_str_dict);
-
-        Ok(CameraRawReadings {
-            colour: data
-                .as_ref()
-                .and_then(|d| d.get("colour"))
-                .and_then(value_to_bytes),
-            annotation: data
-                .as_ref()
-                .and_then(|d| d.get("annotation"))
-                .and_then(value_to_bytes),
-            depth: data
-                .as_ref()
-                .and_then(|d| d.get("depth"))
-                .and_then(value_to_bytes),
+    /// Decode raw readings into typed images using this camera's configuration and the
+    /// layout of the readings' [`source`](CameraRawReadings::source).
+    ///
+    /// The semantic image is only decoded when `classes` is given; fetch it once with
+    /// [`CameraApi::get_annotation_classes`](crate::api::beamng::CameraApi::get_annotation_classes).
+    pub fn decode(
+        &self,
+        raw: &CameraRawReadings,
+        classes: Option<&AnnotationClasses>,
+    ) -> Result<CameraImages> {
+        let resolution = self.config.resolution;
+        let depth_layout = match (raw.source, self.config.integer_depth) {
+            (_, false) => PixelLayout::F32,
+            (BufferSource::SharedMemory, true) => PixelLayout::Rgba8,
+            (BufferSource::Network, true) => PixelLayout::Luma8,
+        };
+        let colour = raw
+            .colour
+            .as_deref()
+            .map(|c| ColourImage::from_raw(c, resolution, PixelLayout::Rgba8))
+            .transpose()?;
+        let depth = raw
+            .depth
+            .as_deref()
+            .map(|d| DepthImage::from_raw(d, resolution, self.config.near_far_planes, depth_layout))
+            .transpose()?;
+        let semantic = match (raw.annotation.as_deref(), classes) {
+            (Some(a), Some(classes)) => Some(SemanticImage::from_raw(a, resolution, classes)?),
+            _ => None,
+        };
+        Ok(CameraImages {
+            colour,
+            depth,
+            semantic,
         })
     }
 
     /// Close the camera sensor and release shared memory.
     pub async fn close(self, bng: &mut BeamNg) -> Result<()> {
-        bng.conn()?
-            .ack(
-                "CloseCamera",
-                "ClosedCamera",
-                &[("name", rmpv::Value::from(self.name.as_str()))],
-            )
-            .await?;
-        info!("Closed Camera: \"{}\"", self.name);
-        // Shared memory buffers are dropped here automatically
-        Ok(())
+        bng.close_sensor(&self.name).await
     }
 
     /// Get the camera name.
@@ -458,8 +580,43 @@ impl Camera {
         &self.name
     }
 
+    /// Get the ID of the vehicle this camera is attached to, if any.
+    pub fn vid(&self) -> Option<&str> {
+        self.vid.as_deref()
+    }
+
     /// Get the camera configuration.
     pub fn config(&self) -> &CameraConfig {
         &self.config
     }
 }
+
+impl GeSensor for Camera {
+    type Config = CameraConfig;
+    type Reading = CameraRawReadings;
+
+    async fn open_sensor(
+        name: impl Into<String> + Send,
+        bng: &mut BeamNg,
+        vehicle: Option<&Vehicle>,
+        config: CameraConfig,
+    ) -> Result<Self> {
+        Camera::open(name, bng, vehicle, config).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn poll_request(&self) -> (&'static str, Vec<(&str, rmpv::Value)>) {
+        ("PollCamera", self.poll_fields())
+    }
+
+    fn decode_poll(&self, resp: &StrDict) -> CameraRawReadings {
+        self.readings_from_poll(resp)
+    }
+
+    async fn close(self, bng: &mut BeamNg) -> Result<()> {
+        Camera::close(self, bng).await
+    }
+}
@@ -0,0 +1,288 @@
+use beamng_proto::types::{value_to_str_dict, Vec3};
+use beamng_proto::{BngError, Result};
+
+use super::{Camera, CameraConfig};
+use crate::beamng::BeamNg;
+
+/// Parse a vector returned either as `{x, y, z}` or as `[x, y, z]`.
+fn parse_vec3(val: &rmpv::Value) -> Option<Vec3> {
+    if let Some(arr) = val.as_array() {
+        if arr.len() < 3 {
+            return None;
+        }
+        return Some((arr[0].as_f64()?, arr[1].as_f64()?, arr[2].as_f64()?));
+    }
+    let map = value_to_str_dict(val.clone())?;
+    Some((
+        map.get("x")?.as_f64()?,
+        map.get("y")?.as_f64()?,
+        map.get("z")?.as_f64()?,
+    ))
+}
+
+/// A camera parameter change. Each sends the same request as the BeamNGpy `Camera`
+/// setter of the same name (`set_position`, `set_max_distance`, ...).
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CameraParam {
+    Position(Vec3),
+    Direction(Vec3),
+    Up(Vec3),
+    FieldOfViewY(f64),
+    MaxDistance(f64),
+    RequestedUpdateTime(f64),
+}
+
+impl CameraParam {
+    /// The request type, acknowledgement type and fields, other than the camera name,
+    /// of the request setting this parameter.
+    fn request(self) -> (&'static str, &'static str, Vec<(&'static str, rmpv::Value)>) {
+        let vec3 = |keys: [&'static str; 3], v: Vec3| {
+            vec![
+                (keys[0], rmpv::Value::from(v.0)),
+                (keys[1], rmpv::Value::from(v.1)),
+                (keys[2], rmpv::Value::from(v.2)),
+            ]
+        };
+        match self {
+            CameraParam::Position(pos) => (
+                "SetCameraSensorPosition",
+                "CompletedSetCameraSensorPosition",
+                vec3(["posX", "posY", "posZ"], pos),
+            ),
+            CameraParam::Direction(dir) => (
+                "SetCameraSensorDirection",
+                "CompletedSetCameraSensorDirection",
+                vec3(["dirX", "dirY", "dirZ"], dir),
+            ),
+            CameraParam::Up(up) => (
+                "SetCameraSensorUp",
+                "CompletedSetCameraSensorUp",
+                vec3(["upX", "upY", "upZ"], up),
+            ),
+            CameraParam::FieldOfViewY(fov_y) => (
+                "SetCameraSensorFovY",
+                "CompletedSetCameraSensorFovY",
+                vec![("fovY", rmpv::Value::from(fov_y))],
+            ),
+            CameraParam::MaxDistance(d) => (
+                "SetCameraMaxDistance",
+                "CompletedSetCameraMaxDistance",
+                vec![("maxDistance", rmpv::Value::from(d))],
+            ),
+            CameraParam::RequestedUpdateTime(t) => (
+                "SetCameraRequestedUpdateTime",
+                "CompletedSetCameraRequestedUpdateTime",
+                vec![("updateTime", rmpv::Value::from(t))],
+            ),
+        }
+    }
+
+    /// Check the value against the camera's current configuration.
+    fn validate(self, config: &CameraConfig) -> Result<()> {
+        match self {
+            CameraParam::FieldOfViewY(fov_y) if !(fov_y > 0.0 && fov_y < 180.0) => {
+                Err(BngError::ValueError(format!(
+                    "Field of view must be in (0, 180) degrees, got {fov_y}"
+                )))
+            }
+            CameraParam::MaxDistance(d) if d <= config.near_far_planes.0 => {
+                Err(BngError::ValueError(format!(
+                    "Max distance {d} must be beyond the near plane {}",
+                    config.near_far_planes.0
+                )))
+            }
+            CameraParam::RequestedUpdateTime(t) if t < 0.0 => Err(BngError::ValueError(format!(
+                "Requested update time must not be negative, got {t}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Record the new value in the local configuration.
+    fn apply(self, config: &mut CameraConfig) {
+        match self {
+            CameraParam::Position(pos) => config.pos = pos,
+            CameraParam::Direction(dir) => config.dir = dir,
+            CameraParam::Up(up) => config.up = up,
+            CameraParam::FieldOfViewY(fov_y) => config.field_of_view_y = fov_y,
+            CameraParam::MaxDistance(d) => config.near_far_planes.1 = d,
+            CameraParam::RequestedUpdateTime(t) => config.requested_update_time = t,
+        }
+    }
+}
+
+impl Camera {
+    /// Validate, send and record a parameter change.
+    async fn set_param(&mut self, bng: &mut BeamNg, param: CameraParam) -> Result<()> {
+        param.validate(&self.config)?;
+        let (req_type, ack_type, fields) = param.request();
+        let mut all = vec![("name", rmpv::Value::from(self.name.as_str()))];
+        all.extend(fields);
+        bng.conn()?.ack(req_type, ack_type, &all).await?;
+        param.apply(&mut self.config);
+        Ok(())
+    }
+
+    /// Send a camera parameter query naming this camera and return its `data` field.
+    async fn get_param(&self, bng: &mut BeamNg, req_type: &str) -> Result<Option<rmpv::Value>> {
+        let mut resp = bng
+            .conn()?
+            .request(req_type, &[("name", rmpv::Value::from(self.name.as_str()))])
+            .await?;
+        Ok(resp.remove("data"))
+    }
+
+    async fn get_vec3(&self, bng: &mut BeamNg, req_type: &str) -> Result<Vec3> {
+        self.get_param(bng, req_type)
+            .await?
+            .as_ref()
+            .and_then(parse_vec3)
+            .ok_or_else(|| BngError::ValueError(format!("Missing vector in {req_type} response")))
+    }
+
+    async fn get_f64(&self, bng: &mut BeamNg, req_type: &str) -> Result<f64> {
+        self.get_param(bng, req_type)
+            .await?
+            .and_then(|v| v.as_f64())
+            .ok_or_else(|| BngError::ValueError(format!("Missing value in {req_type} response")))
+    }
+
+    /// Move the camera. The position is relative to the vehicle for attached cameras.
+    pub async fn set_position(&mut self, bng: &mut BeamNg, pos: Vec3) -> Result<()> {
+        self.set_param(bng, CameraParam::Position(pos)).await
+    }
+
+    /// Point the camera along `dir`.
+    pub async fn set_direction(&mut self, bng: &mut BeamNg, dir: Vec3) -> Result<()> {
+        self.set_param(bng, CameraParam::Direction(dir)).await
+    }
+
+    /// Set the camera's up vector.
+    pub async fn set_up(&mut self, bng: &mut BeamNg, up: Vec3) -> Result<()> {
+        self.set_param(bng, CameraParam::Up(up)).await
+    }
+
+    /// Set the vertical field of view in degrees, which also changes the
+    /// [`intrinsics`](Self::intrinsics) derived from it.
+    ///
+    /// BeamNGpy has no counterpart, and the simulator offers no matching getter, so
+    /// the value is only read back from the local config (see
+    /// [`field_of_view_y`](Self::field_of_view_y)). A simulator that does not handle
+    /// `SetCameraSensorFovY` answers with an error, which is returned with the local
+    /// config left unchanged.
+    pub async fn set_field_of_view_y(&mut self, bng: &mut BeamNg, fov_y: f64) -> Result<()> {
+        self.set_param(bng, CameraParam::FieldOfViewY(fov_y)).await
+    }
+
+    /// The vertical field of view in degrees, as opened or last set.
+    pub fn field_of_view_y(&self) -> f64 {
+        self.config.field_of_view_y
+    }
+
+    /// Set the maximum render distance (the far plane) in metres.
+    pub async fn set_max_distance(&mut self, bng: &mut BeamNg, max_distance: f64) -> Result<()> {
+        self.set_param(bng, CameraParam::MaxDistance(max_distance))
+            .await
+    }
+
+    /// Set how often, in seconds, the simulator renders this camera.
+    pub async fn set_requested_update_time(
+        &mut self,
+        bng: &mut BeamNg,
+        update_time: f64,
+    ) -> Result<()> {
+        self.set_param(bng, CameraParam::RequestedUpdateTime(update_time))
+            .await
+    }
+
+    /// Query the simulator for the camera's current position, updating the local config.
+    pub async fn get_position(&mut self, bng: &mut BeamNg) -> Result<Vec3> {
+        let pos = self.get_vec3(bng, "GetCameraSensorPosition").await?;
+        self.config.pos = pos;
+        Ok(pos)
+    }
+
+    /// Query the simulator for the camera's current direction, updating the local config.
+    pub async fn get_direction(&mut self, bng: &mut BeamNg) -> Result<Vec3> {
+        let dir = self.get_vec3(bng, "GetCameraSensorDirection").await?;
+        self.config.dir = dir;
+        Ok(dir)
+    }
+
+    /// Query the simulator for the camera's current up vector, updating the local config.
+    pub async fn get_up(&mut self, bng: &mut BeamNg) -> Result<Vec3> {
+        let up = self.get_vec3(bng, "GetCameraSensorUp").await?;
+        self.config.up = up;
+        Ok(up)
+    }
+
+    /// Query the simulator for the camera's maximum render distance, updating the local
+    /// config.
+    pub async fn get_max_distance(&mut self, bng: &mut BeamNg) -> Result<f64> {
+        let max_distance = self.get_f64(bng, "GetCameraMaxDistance").await?;
+        self.config.near_far_planes.1 = max_distance;
+        Ok(max_distance)
+    }
+
+    /// Query the simulator for the camera's current update time, updating the local config.
+    pub async fn get_requested_update_time(&mut self, bng: &mut BeamNg) -> Result<f64> {
+        let update_time = self.get_f64(bng, "GetCameraRequestedUpdateTime").await?;
+        self.config.requested_update_time = update_time;
+        Ok(update_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vec3_forms() {
+        let map = rmpv::Value::Map(vec![
+            (rmpv::Value::from("x"), rmpv::Value::from(1.0)),
+            (rmpv::Value::from("y"), rmpv::Value::from(2.0)),
+            (rmpv::Value::from("z"), rmpv::Value::from(3.0)),
+        ]);
+        assert_eq!(parse_vec3(&map), Some((1.0, 2.0, 3.0)));
+        let arr = rmpv::Value::Array(vec![
+            rmpv::Value::from(4.0),
+            rmpv::Value::from(5.0),
+            rmpv::Value::from(6.0),
+        ]);
+        assert_eq!(parse_vec3(&arr), Some((4.0, 5.0, 6.0)));
+        assert_eq!(parse_vec3(&rmpv::Value::Nil), None);
+    }
+
+    #[test]
+    fn test_param_requests() {
+        let (req, ack, fields) = CameraParam::Direction((0.0, 1.0, 0.0)).request();
+        assert_eq!(
+            (req, ack),
+            (
+                "SetCameraSensorDirection",
+                "CompletedSetCameraSensorDirection"
+            )
+        );
+        let keys: Vec<_> = fields.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec!["dirX", "dirY", "dirZ"]);
+        assert_eq!(fields[1].1, rmpv::Value::from(1.0));
+
+        let mut config = CameraConfig::default();
+        let far = CameraParam::MaxDistance(250.0);
+        assert_eq!(far.request().2[0].0, "maxDistance");
+        assert!(far.validate(&config).is_ok());
+        far.apply(&mut config);
+        assert_eq!(config.near_far_planes.1, 250.0);
+        let near = config.near_far_planes.0;
+        assert!(CameraParam::MaxDistance(near).validate(&config).is_err());
+        let fov = CameraParam::FieldOfViewY(90.0);
+        assert_eq!(fov.request().0, "SetCameraSensorFovY");
+        assert!(fov.validate(&config).is_ok());
+        fov.apply(&mut config);
+        assert_eq!(config.field_of_view_y, 90.0);
+        assert!(CameraParam::FieldOfViewY(180.0).validate(&config).is_err());
+        assert!(CameraParam::RequestedUpdateTime(-1.0)
+            .validate(&config)
+            .is_err());
+    }
+}
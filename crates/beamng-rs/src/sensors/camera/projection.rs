@@ -0,0 +1,349 @@
+use beamng_proto::types::{StrDict, Vec3};
+use beamng_proto::{BngError, Result};
+
+use super::images::DepthImage;
+use super::{Camera, CameraConfig};
+
+fn add(a: Vec3, b: Vec3) -> Vec3 {
+    (a.0 + b.0, a.1 + b.1, a.2 + b.2)
+}
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    (a.0 - b.0, a.1 - b.1, a.2 - b.2)
+}
+
+fn scale(a: Vec3, s: f64) -> Vec3 {
+    (a.0 * s, a.1 * s, a.2 * s)
+}
+
+fn dot(a: Vec3, b: Vec3) -> f64 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    (
+        a.1 * b.2 - a.2 * b.1,
+        a.2 * b.0 - a.0 * b.2,
+        a.0 * b.1 - a.1 * b.0,
+    )
+}
+
+fn normalize(a: Vec3) -> Vec3 {
+    let len = dot(a, a).sqrt();
+    if len > f64::EPSILON {
+        scale(a, 1.0 / len)
+    } else {
+        a
+    }
+}
+
+/// An orthonormal frame: an origin plus three axes, all expressed in a parent frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoordinateFrame {
+    pub origin: Vec3,
+    pub x: Vec3,
+    pub y: Vec3,
+    pub z: Vec3,
+}
+
+impl CoordinateFrame {
+    /// Transform a point from this frame into the parent frame.
+    pub fn to_parent(&self, p: Vec3) -> Vec3 {
+        add(
+            self.origin,
+            add(
+                add(scale(self.x, p.0), scale(self.y, p.1)),
+                scale(self.z, p.2),
+            ),
+        )
+    }
+
+    /// Transform a point from the parent frame into this frame.
+    pub fn from_parent(&self, p: Vec3) -> Vec3 {
+        let d = sub(p, self.origin);
+        (dot(d, self.x), dot(d, self.y), dot(d, self.z))
+    }
+
+    /// Rotate a direction from this frame into the parent frame.
+    fn rotate_to_parent(&self, d: Vec3) -> Vec3 {
+        add(
+            add(scale(self.x, d.0), scale(self.y, d.1)),
+            scale(self.z, d.2),
+        )
+    }
+}
+
+/// The world pose of a vehicle, as reported by the [`State`](crate::sensors::State) sensor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VehiclePose {
+    pub pos: Vec3,
+    /// Forward direction (unit vector).
+    pub dir: Vec3,
+    /// Up direction (unit vector).
+    pub up: Vec3,
+}
+
+impl VehiclePose {
+    /// Parse a vehicle state dict containing `pos`, `dir` and `up` arrays.
+    pub fn from_state(state: &StrDict) -> Option<Self> {
+        let vec3 = |key: &str| -> Option<Vec3> {
+            let arr = state.get(key)?.as_array()?;
+            if arr.len() < 3 {
+                return None;
+            }
+            Some((arr[0].as_f64()?, arr[1].as_f64()?, arr[2].as_f64()?))
+        };
+        Some(Self {
+            pos: vec3("pos")?,
+            dir: vec3("dir")?,
+            up: vec3("up")?,
+        })
+    }
+
+    /// The vehicle's local frame in world coordinates.
+    ///
+    /// BeamNG vehicles face `-y` with `+z` up, so the local axes are
+    /// `x = up × dir`, `y = -dir` and `z = up`.
+    pub fn frame(&self) -> CoordinateFrame {
+        let dir = normalize(self.dir);
+        let up = normalize(sub(self.up, scale(dir, dot(self.up, dir))));
+        CoordinateFrame {
+            origin: self.pos,
+            x: cross(up, dir),
+            y: scale(dir, -1.0),
+            z: up,
+        }
+    }
+}
+
+/// The coordinate frame points are expressed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointFrame {
+    /// Camera frame: `x` right, `y` down, `z` along the viewing direction (OpenCV convention).
+    Camera,
+    /// The attached vehicle's local frame (the frame `CameraConfig::pos` is given in).
+    Vehicle,
+    /// World frame.
+    World,
+}
+
+/// Pinhole intrinsics of a camera, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraIntrinsics {
+    pub width: u32,
+    pub height: u32,
+    pub fx: f64,
+    pub fy: f64,
+    pub cx: f64,
+    pub cy: f64,
+}
+
+impl CameraIntrinsics {
+    /// Derive intrinsics from a resolution and vertical field of view in degrees,
+    /// assuming square pixels and a centred principal point.
+    pub fn from_fov(resolution: (u32, u32), field_of_view_y: f64) -> Self {
+        let (width, height) = resolution;
+        let fy = (height as f64 / 2.0) / (field_of_view_y.to_radians() / 2.0).tan();
+        Self {
+            width,
+            height,
+            fx: fy,
+            fy,
+            cx: width as f64 / 2.0,
+            cy: height as f64 / 2.0,
+        }
+    }
+
+    /// The 3x3 camera matrix `K`, row-major.
+    pub fn matrix(&self) -> [f64; 9] {
+        [self.fx, 0.0, self.cx, 0.0, self.fy, self.cy, 0.0, 0.0, 1.0]
+    }
+
+    /// Project a camera-frame point to pixel coordinates.
+    ///
+    /// Returns `None` for points behind the camera.
+    pub fn project(&self, p: Vec3) -> Option<(f64, f64)> {
+        if p.2 <= f64::EPSILON {
+            return None;
+        }
+        Some((self.fx * p.0 / p.2 + self.cx, self.fy * p.1 / p.2 + self.cy))
+    }
+
+    /// Unproject pixel coordinates at the given depth (distance along the viewing axis)
+    /// to a camera-frame point.
+    pub fn unproject(&self, u: f64, v: f64, depth: f64) -> Vec3 {
+        (
+            (u - self.cx) / self.fx * depth,
+            (v - self.cy) / self.fy * depth,
+            depth,
+        )
+    }
+
+    /// Whether pixel coordinates lie inside the image.
+    pub fn contains(&self, u: f64, v: f64) -> bool {
+        u >= 0.0 && v >= 0.0 && u < self.width as f64 && v < self.height as f64
+    }
+}
+
+/// The camera frame expressed in its parent frame (the vehicle frame when attached,
+/// otherwise the world frame).
+pub(crate) fn camera_frame(config: &CameraConfig) -> CoordinateFrame {
+    let z = normalize(config.dir);
+    let up = normalize(sub(config.up, scale(z, dot(config.up, z))));
+    let x = cross(z, up);
+    CoordinateFrame {
+        origin: config.pos,
+        x,
+        y: scale(up, -1.0),
+        z,
+    }
+}
+
+impl Camera {
+    /// The pinhole intrinsics derived from `resolution` and `field_of_view_y`.
+    pub fn intrinsics(&self) -> CameraIntrinsics {
+        CameraIntrinsics::from_fov(self.config.resolution, self.config.field_of_view_y)
+    }
+
+    /// The camera frame expressed in the vehicle frame (or the world frame for
+    /// cameras not attached to a vehicle).
+    pub fn extrinsics(&self) -> CoordinateFrame {
+        camera_frame(&self.config)
+    }
+
+    /// The camera frame expressed in the world frame.
+    ///
+    /// `vehicle_pose` is required for cameras attached to a vehicle. With
+    /// `is_dir_world_space`, the orientation is taken as world-space while the position
+    /// stays relative to the vehicle.
+    pub fn world_frame(&self, vehicle_pose: Option<&VehiclePose>) -> Result<CoordinateFrame> {
+        let local = self.extrinsics();
+        if self.vid.is_none() {
+            return Ok(local);
+        }
+        let vehicle = vehicle_pose
+            .ok_or_else(|| {
+                BngError::ValueError(format!(
+                    "Camera \"{}\" is attached to a vehicle; its pose is required",
+                    self.name
+                ))
+            })?
+            .frame();
+        if self.config.is_dir_world_space {
+            return Ok(CoordinateFrame {
+                origin: vehicle.to_parent(local.origin),
+                ..local
+            });
+        }
+        Ok(CoordinateFrame {
+            origin: vehicle.to_parent(local.origin),
+            x: vehicle.rotate_to_parent(local.x),
+            y: vehicle.rotate_to_parent(local.y),
+            z: vehicle.rotate_to_parent(local.z),
+        })
+    }
+
+    /// Unproject a depth image into a point cloud.
+    ///
+    /// Depth values are interpreted as distance along the viewing axis; pixels at or
+    /// beyond the far plane (no hit) are skipped. `vehicle_pose` is only needed for
+    /// [`PointFrame::World`] on vehicle-attached cameras.
+    pub fn depth_to_points(
+        &self,
+        depth: &DepthImage,
+        frame: PointFrame,
+        vehicle_pose: Option<&VehiclePose>,
+    ) -> Result<Vec<Vec3>> {
+        let intrinsics = self.intrinsics();
+        if (depth.width, depth.height) != (intrinsics.width, intrinsics.height) {
+            return Err(BngError::ValueError(format!(
+                "Depth image is {}x{}, camera resolution is {}x{}",
+                depth.width, depth.height, intrinsics.width, intrinsics.height
+            )));
+        }
+        let target = match frame {
+            PointFrame::Camera => None,
+            PointFrame::Vehicle => Some(self.extrinsics()),
+            PointFrame::World => Some(self.world_frame(vehicle_pose)?),
+        };
+        let far = self.config.near_far_planes.1;
+
+        let mut points = Vec::with_capacity(depth.data.len());
+        for (i, &d) in depth.data.iter().enumerate() {
+            let d = d as f64;
+            if !d.is_finite() || d <= 0.0 || d >= far {
+                continue;
+            }
+            let u = (i % depth.width as usize) as f64 + 0.5;
+            let v = (i / depth.width as usize) as f64 + 0.5;
+            let p = intrinsics.unproject(u, v, d);
+            points.push(match &target {
+                Some(f) => f.to_parent(p),
+                None => p,
+            });
+        }
+        Ok(points)
+    }
+
+    /// Project world points to pixel coordinates.
+    ///
+    /// Returns `None` for points behind the camera or outside the image.
+    pub fn project_world_points(
+        &self,
+        points: &[Vec3],
+        vehicle_pose: Option<&VehiclePose>,
+    ) -> Result<Vec<Option<(f64, f64)>>> {
+        let intrinsics = self.intrinsics();
+        let frame = self.world_frame(vehicle_pose)?;
+        Ok(points
+            .iter()
+            .map(|&p| {
+                intrinsics
+                    .project(frame.from_parent(p))
+                    .filter(|&(u, v)| intrinsics.contains(u, v))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vec3, b: Vec3) -> bool {
+        let d = sub(a, b);
+        dot(d, d).sqrt() < 1e-9
+    }
+
+    #[test]
+    fn test_project_unproject_roundtrip() {
+        let k = CameraIntrinsics::from_fov((640, 480), 90.0);
+        assert!((k.fy - 240.0).abs() < 1e-9);
+        let p = k.unproject(100.0, 400.0, 7.5);
+        let (u, v) = k.project(p).unwrap();
+        assert!((u - 100.0).abs() < 1e-9 && (v - 400.0).abs() < 1e-9);
+        assert!(k.project((0.0, 0.0, -1.0)).is_none());
+    }
+
+    #[test]
+    fn test_frames() {
+        // Default camera looks forward (-y) with +z up.
+        let cam = camera_frame(&CameraConfig {
+            pos: (0.0, 0.0, 1.0),
+            ..Default::default()
+        });
+        assert!(close(cam.to_parent((0.0, 0.0, 2.0)), (0.0, -2.0, 1.0)));
+        assert!(close(cam.to_parent((0.0, -1.0, 0.0)), (0.0, 0.0, 2.0)));
+
+        // A vehicle facing world +x maps its local -y onto +x.
+        let pose = VehiclePose {
+            pos: (10.0, 0.0, 0.0),
+            dir: (1.0, 0.0, 0.0),
+            up: (0.0, 0.0, 1.0),
+        };
+        let f = pose.frame();
+        assert!(close(f.to_parent((0.0, -3.0, 0.0)), (13.0, 0.0, 0.0)));
+        let p = (4.0, -2.0, 5.0);
+        assert!(close(f.from_parent(f.to_parent(p)), p));
+    }
+}
@@ -0,0 +1,250 @@
+use beamng_proto::Result;
+use tracing::{info, warn};
+
+use super::stream::sim_time;
+use super::{Camera, CameraConfig, CameraRawReadings};
+use crate::beamng::BeamNg;
+use crate::vehicle::Vehicle;
+
+/// How a [`CameraRig`] triggers its cameras.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RigTrigger {
+    /// Request a fresh render from every camera (`SendAdHocRequestCamera`) before
+    /// collecting any of them.
+    #[default]
+    AdHoc,
+    /// Send one `PollCamera` per camera back-to-back without waiting, then read all
+    /// the responses.
+    Pipelined,
+}
+
+/// The frames of every camera in a rig, captured at one simulation instant.
+#[derive(Debug, Default)]
+pub struct RigCapture {
+    /// Simulation time in seconds of the capture, if a clock vehicle was given.
+    pub sim_time: Option<f64>,
+    /// `(name, readings)` for each camera, in the order the rig was opened with.
+    pub frames: Vec<(String, CameraRawReadings)>,
+}
+
+impl RigCapture {
+    /// Get the readings of a camera by name.
+    pub fn get(&self, name: &str) -> Option<&CameraRawReadings> {
+        self.frames
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, readings)| readings)
+    }
+}
+
+/// A set of cameras mounted on one vehicle and captured together.
+///
+/// Captures are only simultaneous if the simulation does not advance in between,
+/// so pause the simulation and advance it with [`step`](Self::step).
+///
+/// Dropping the rig without calling [`close`](Self::close) queues the cameras to be
+/// closed before the next [`ControlApi::step`](crate::api::beamng::ControlApi::step),
+/// or explicitly with [`BeamNg::flush_pending_closes`].
+///
+/// # Example
+/// ```no_run
+/// # async fn example(bng: &mut beamng_rs::BeamNg, ego: &mut beamng_rs::vehicle::Vehicle) -> beamng_proto::Result<()> {
+/// use beamng_rs::sensors::{CameraConfig, CameraRig, RigTrigger};
+///
+/// let rig = CameraRig::open(bng, ego, vec![
+///     ("front", CameraConfig { pos: (0.0, -2.0, 1.5), ..Default::default() }),
+///     ("rear", CameraConfig { pos: (0.0, 2.0, 1.5), dir: (0.0, 1.0, 0.0), ..Default::default() }),
+/// ], RigTrigger::AdHoc).await?;
+/// bng.control().pause().await?;
+/// let capture = rig.step(bng, 10, Some(ego)).await?;
+/// println!("{} frames at {:?}", capture.frames.len(), capture.sim_time);
+/// rig.close(bng).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CameraRig {
+    cameras: Vec<Camera>,
+    trigger: RigTrigger,
+}
+
+impl CameraRig {
+    /// Open one camera per `(name, config)` entry, attached to `vehicle`.
+    ///
+    /// Camera positions and directions are relative to the vehicle. If any camera
+    /// fails to open, the ones already opened are closed again.
+    pub async fn open<S: Into<String>>(
+        bng: &mut BeamNg,
+        vehicle: &Vehicle,
+        cameras: impl IntoIterator<Item = (S, CameraConfig)>,
+        trigger: RigTrigger,
+    ) -> Result<Self> {
+        let mut rig = CameraRig {
+            cameras: Vec::new(),
+            trigger,
+        };
+        for (name, config) in cameras {
+            match Camera::open(name, bng, Some(vehicle), config).await {
+                Ok(camera) => rig.cameras.push(camera),
+                Err(e) => {
+                    if let Err(close_err) = rig.close(bng).await {
+                        warn!(
+                            "Failed to close CameraRig after a camera failed to open: {close_err}"
+                        );
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        info!("Opened CameraRig with {} cameras", rig.cameras.len());
+        Ok(rig)
+    }
+
+    /// Trigger every camera and collect one frame from each.
+    ///
+    /// If `clock` is given, the capture is tagged with the simulation time read from a
+    /// [`Timer`](crate::sensors::Timer) over that vehicle's connection.
+    pub async fn capture(
+        &self,
+        bng: &mut BeamNg,
+        clock: Option<&mut Vehicle>,
+    ) -> Result<RigCapture> {
+        let readings = match self.trigger {
+            RigTrigger::AdHoc => self.capture_ad_hoc(bng).await?,
+            RigTrigger::Pipelined => self.capture_pipelined(bng).await?,
+        };
+        Ok(RigCapture {
+            sim_time: sim_time(clock).await?,
+            frames: self
+                .cameras
+                .iter()
+                .map(|c| c.name().to_string())
+                .zip(readings)
+                .collect(),
+        })
+    }
+
+    /// Advance the simulation by `count` steps, wait for it to finish, then
+    /// [`capture`](Self::capture).
+    pub async fn step(
+        &self,
+        bng: &mut BeamNg,
+        count: u32,
+        clock: Option<&mut Vehicle>,
+    ) -> Result<RigCapture> {
+        bng.control().step(count, true).await?;
+        self.capture(bng, clock).await
+    }
+
+    async fn capture_ad_hoc(&self, bng: &mut BeamNg) -> Result<Vec<CameraRawReadings>> {
+        let mut request_ids = Vec::with_capacity(self.cameras.len());
+        for camera in &self.cameras {
+            request_ids.push(camera.send_ad_hoc_request(bng).await?);
+        }
+        let mut readings = Vec::with_capacity(self.cameras.len());
+        for (camera, request_id) in self.cameras.iter().zip(request_ids) {
+            readings.push(camera.collect_ad_hoc_request(bng, request_id).await?);
+        }
+        Ok(readings)
+    }
+
+    async fn capture_pipelined(&self, bng: &mut BeamNg) -> Result<Vec<CameraRawReadings>> {
+        let conn = bng.conn()?;
+        let mut request_ids = Vec::with_capacity(self.cameras.len());
+        for camera in &self.cameras {
+            request_ids.push(conn.send_raw("PollCamera", &camera.poll_fields()).await?);
+        }
+        let mut readings = Vec::with_capacity(self.cameras.len());
+        for (camera, request_id) in self.cameras.iter().zip(request_ids) {
+            let resp = conn.recv(request_id).await?;
+            readings.push(camera.readings_from_poll(&resp));
+        }
+        Ok(readings)
+    }
+
+    /// The cameras in the rig, in the order they were opened.
+    pub fn cameras(&self) -> &[Camera] {
+        &self.cameras
+    }
+
+    /// Get a camera by name.
+    pub fn camera(&self, name: &str) -> Option<&Camera> {
+        self.cameras.iter().find(|c| c.name() == name)
+    }
+
+    /// Get a camera by name, e.g. to move it with [`Camera::set_position`].
+    pub fn camera_mut(&mut self, name: &str) -> Option<&mut Camera> {
+        self.cameras.iter_mut().find(|c| c.name() == name)
+    }
+
+    pub fn trigger(&self) -> RigTrigger {
+        self.trigger
+    }
+
+    /// Close every camera in the rig.
+    ///
+    /// All cameras are closed even if some fail; the first error is returned.
+    pub async fn close(self, bng: &mut BeamNg) -> Result<()> {
+        let mut result = Ok(());
+        for camera in self.cameras {
+            let closed = camera.close(bng).await;
+            if result.is_ok() {
+                result = closed;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::registry::SharedSensorRegistry;
+    use crate::sensors::{SensorInfo, SensorKind};
+
+    fn camera(name: &str, registry: &SharedSensorRegistry) -> Camera {
+        Camera {
+            name: name.into(),
+            vid: Some("ego".into()),
+            config: CameraConfig::default(),
+            colour_shmem: None,
+            annotation_shmem: None,
+            depth_shmem: None,
+            registry: registry.clone(),
+        }
+    }
+
+    #[test]
+    fn test_rig_lookup_and_drop() {
+        let registry = SharedSensorRegistry::default();
+        for name in ["front", "rear"] {
+            registry.lock().unwrap().insert(SensorInfo {
+                name: name.into(),
+                kind: SensorKind::Camera,
+                vid: Some("ego".into()),
+            });
+        }
+        let rig = CameraRig {
+            cameras: vec![camera("front", &registry), camera("rear", &registry)],
+            trigger: RigTrigger::Pipelined,
+        };
+        assert_eq!(rig.camera("rear").map(Camera::name), Some("rear"));
+        assert!(rig.camera("side").is_none());
+
+        let capture = RigCapture {
+            sim_time: Some(1.0),
+            frames: vec![("front".into(), CameraRawReadings::default())],
+        };
+        assert!(capture.get("front").is_some() && capture.get("rear").is_none());
+
+        // Dropping the rig without closing queues both cameras for closing.
+        drop(rig);
+        let pending: Vec<_> = registry
+            .lock()
+            .unwrap()
+            .take_pending()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(pending, vec!["front", "rear"]);
+    }
+}
@@ -0,0 +1,236 @@
+use std::time::Duration;
+
+use beamng_proto::{BngError, Result};
+use futures_util::Stream;
+use tokio::time::Instant;
+
+use super::{Camera, CameraRawReadings};
+use crate::beamng::BeamNg;
+use crate::sensors::Timer;
+use crate::vehicle::Vehicle;
+
+/// Options for [`Camera::frames`].
+#[derive(Debug, Clone)]
+pub struct FrameStreamOptions {
+    /// Maximum number of frames per second to deliver. `None` delivers frames as fast
+    /// as new ones arrive.
+    pub target_rate: Option<f64>,
+    /// How long to wait before re-reading when the simulator has not produced a new frame.
+    pub poll_interval: Duration,
+    /// How long to wait for a new frame before the stream ends with
+    /// [`BngError::Timeout`], e.g. because the simulation is paused. `None` waits forever.
+    pub max_wait: Option<Duration>,
+}
+
+impl Default for FrameStreamOptions {
+    fn default() -> Self {
+        Self {
+            target_rate: None,
+            poll_interval: Duration::from_millis(1),
+            max_wait: Some(Duration::from_secs(10)),
+        }
+    }
+}
+
+/// A single, distinct camera frame.
+#[derive(Debug, Clone)]
+pub struct CameraFrame {
+    /// Position of this frame within its stream, starting at 0. This is counted
+    /// locally; use [`sim_time`](Self::sim_time) to identify the frame in the simulation.
+    pub seq: u64,
+    /// Simulation time in seconds at which the frame was read, if a clock vehicle was
+    /// given. It is read just before the image buffers.
+    pub sim_time: Option<f64>,
+    pub readings: CameraRawReadings,
+}
+
+/// Read the simulation time from a [`Timer`] over the clock vehicle's connection.
+pub(super) async fn sim_time(clock: Option<&mut Vehicle>) -> Result<Option<f64>> {
+    let Some(vehicle) = clock else {
+        return Ok(None);
+    };
+    let time = vehicle
+        .poll_sensor("timer", &Timer)
+        .await?
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| BngError::ValueError("Missing time in Timer response".into()))?;
+    Ok(Some(time))
+}
+
+/// Decides which reads are new frames and when the next one may be delivered.
+#[derive(Debug, Default)]
+struct FramePacer {
+    target_rate: Option<f64>,
+    last_time: Option<f64>,
+    next_deadline: Option<Instant>,
+}
+
+impl FramePacer {
+    fn new(target_rate: Option<f64>) -> Self {
+        Self {
+            target_rate: target_rate.filter(|r| *r > 0.0),
+            ..Self::default()
+        }
+    }
+
+    /// Whether a read at simulation time `time` is a new frame. Without a clock every
+    /// read counts as new.
+    fn is_new(&self, time: Option<f64>) -> bool {
+        time.is_none()
+            || self
+                .last_time
+                .is_none_or(|last| time.is_some_and(|t| t > last))
+    }
+
+    /// Record a delivered frame and schedule the next delivery.
+    ///
+    /// The next deadline is counted from `now` rather than from the previous deadline,
+    /// so a slow consumer gets the latest frame instead of a burst of stale ones.
+    fn deliver(&mut self, time: Option<f64>, now: Instant) {
+        self.last_time = time.or(self.last_time);
+        self.next_deadline = self
+            .target_rate
+            .map(|rate| now + Duration::from_secs_f64(1.0 / rate));
+    }
+}
+
+struct FrameStream<'a> {
+    camera: &'a Camera,
+    bng: &'a mut BeamNg,
+    clock: Option<&'a mut Vehicle>,
+    options: FrameStreamOptions,
+    pacer: FramePacer,
+    next_seq: u64,
+    done: bool,
+}
+
+impl FrameStream<'_> {
+    async fn read(&mut self) -> Result<CameraRawReadings> {
+        let config = self.camera.config();
+        if config.is_streaming && config.is_using_shared_memory {
+            self.camera.stream_raw()
+        } else {
+            self.camera.poll_raw(self.bng).await
+        }
+    }
+
+    async fn next_frame(&mut self) -> Result<CameraFrame> {
+        if let Some(deadline) = self.pacer.next_deadline {
+            tokio::time::sleep_until(deadline).await;
+        }
+
+        // Only the clock is read until the simulation has moved on, so a paused
+        // simulation costs one Timer request per poll interval and no image reads.
+        let started = Instant::now();
+        let sim_time = loop {
+            let time = sim_time(self.clock.as_deref_mut()).await?;
+            if self.pacer.is_new(time) {
+                break time;
+            }
+            if let Some(max_wait) = self.options.max_wait {
+                if started.elapsed() >= max_wait {
+                    return Err(BngError::Timeout(format!(
+                        "No new frame from camera \"{}\" within {max_wait:?}",
+                        self.camera.name()
+                    )));
+                }
+            }
+            tokio::time::sleep(self.options.poll_interval).await;
+        };
+        let readings = self.read().await?;
+        self.pacer.deliver(sim_time, Instant::now());
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        Ok(CameraFrame {
+            seq,
+            sim_time,
+            readings,
+        })
+    }
+}
+
+impl Camera {
+    /// Stream distinct frames from this camera.
+    ///
+    /// Frames are read from shared memory when the camera is streaming, and with
+    /// [`poll_raw`](Self::poll_raw) otherwise. Frames are only read when the consumer asks
+    /// for the next one, so a slow consumer always receives the most recent frame and
+    /// stale ones are dropped.
+    ///
+    /// If `clock` is given, each frame is tagged with the simulation time read from a
+    /// [`Timer`] over that vehicle's connection, and a frame is only delivered once that
+    /// time has advanced past the previous frame's. If the simulation does not advance
+    /// within [`max_wait`](FrameStreamOptions::max_wait) the stream yields a
+    /// [`BngError::Timeout`]. Without a clock every read is delivered, paced only by
+    /// [`target_rate`](FrameStreamOptions::target_rate). The stream ends after yielding
+    /// an error.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example(bng: &mut beamng_rs::BeamNg, camera: &beamng_rs::sensors::Camera, ego: &mut beamng_rs::vehicle::Vehicle) -> beamng_proto::Result<()> {
+    /// use beamng_rs::sensors::FrameStreamOptions;
+    /// use futures_util::StreamExt;
+    ///
+    /// let frames = camera.frames(bng, Some(ego), FrameStreamOptions::default());
+    /// futures_util::pin_mut!(frames);
+    /// while let Some(frame) = frames.next().await {
+    ///     let frame = frame?;
+    ///     println!("frame {} at {:?}", frame.seq, frame.sim_time);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn frames<'a>(
+        &'a self,
+        bng: &'a mut BeamNg,
+        clock: Option<&'a mut Vehicle>,
+        options: FrameStreamOptions,
+    ) -> impl Stream<Item = Result<CameraFrame>> + 'a {
+        let state = FrameStream {
+            camera: self,
+            bng,
+            clock,
+            pacer: FramePacer::new(options.target_rate),
+            options,
+            next_seq: 0,
+            done: false,
+        };
+        futures_util::stream::unfold(state, |mut state| async move {
+            if state.done {
+                return None;
+            }
+            let frame = state.next_frame().await;
+            state.done = frame.is_err();
+            Some((frame, state))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pacer_suppresses_duplicates_and_paces() {
+        let now = Instant::now();
+        let mut pacer = FramePacer::new(Some(20.0));
+        assert!(pacer.is_new(Some(1.0)));
+        pacer.deliver(Some(1.0), now);
+        assert_eq!(pacer.next_deadline, Some(now + Duration::from_millis(50)));
+        // A paused simulation reports the same time again.
+        assert!(!pacer.is_new(Some(1.0)));
+        assert!(!pacer.is_new(Some(0.5)));
+        assert!(pacer.is_new(Some(1.05)));
+
+        let later = now + Duration::from_secs(1);
+        pacer.deliver(Some(1.05), later);
+        assert_eq!(pacer.next_deadline, Some(later + Duration::from_millis(50)));
+
+        // Without a clock or a rate, every read is delivered immediately.
+        let mut pacer = FramePacer::new(None);
+        pacer.deliver(None, now);
+        assert!(pacer.is_new(None));
+        assert_eq!(pacer.next_deadline, None);
+    }
+}
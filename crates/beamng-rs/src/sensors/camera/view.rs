@@ -0,0 +1,132 @@
+use beamng_proto::Result;
+
+use super::{BufferSource, Camera, CameraRawReadings, ShmemBuffer};
+use crate::beamng::BeamNg;
+
+/// A borrowed, uncopied view of one shared-memory image buffer.
+///
+/// The simulator owns the writing side of the segment and may overwrite it at any
+/// time while rendering. [`copy_into`](Self::copy_into) and [`to_vec`](Self::to_vec)
+/// copy it out with volatile reads rather than through a Rust reference, so a
+/// concurrent render cannot cause undefined behaviour, although the copy may then mix
+/// two frames. Borrowing the bytes in place with [`as_slice`](Self::as_slice) is
+/// `unsafe` and only sound while the simulator is not rendering this camera.
+pub struct ShmemView<'a> {
+    buffer: &'a ShmemBuffer,
+}
+
+impl ShmemView<'_> {
+    /// Size of the buffer in bytes.
+    pub fn len(&self) -> usize {
+        self.buffer.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.size == 0
+    }
+
+    /// Borrow the buffer in place, without copying.
+    ///
+    /// # Safety
+    /// The simulator must not write to the buffer while the returned slice is alive.
+    /// Pausing the simulation is not enough: the renderer keeps drawing streaming
+    /// cameras while paused. The caller has to know that nothing renders this camera
+    /// for the slice's lifetime; otherwise use [`copy_into`](Self::copy_into).
+    pub unsafe fn as_slice(&self) -> &[u8] {
+        self.buffer.as_slice()
+    }
+
+    /// Copy the buffer into `buf`, reusing its allocation. A render that runs during the
+    /// copy may leave it with parts of two frames.
+    pub fn copy_into(&self, buf: &mut Vec<u8>) {
+        self.buffer.read_into(buf);
+    }
+
+    /// Copy the buffer into a new `Vec`.
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.buffer.read()
+    }
+}
+
+/// Borrowed views of a camera's shared-memory buffers. See [`ShmemView`] for the
+/// safety rules.
+pub struct CameraRawView<'a> {
+    pub colour: Option<ShmemView<'a>>,
+    pub annotation: Option<ShmemView<'a>>,
+    pub depth: Option<ShmemView<'a>>,
+}
+
+/// Copy a shared-memory buffer into an optional output, reusing any existing allocation.
+fn read_slot(shmem: Option<&ShmemBuffer>, slot: &mut Option<Vec<u8>>) {
+    match shmem {
+        Some(s) => s.read_into(slot.get_or_insert_with(Vec::new)),
+        None => *slot = None,
+    }
+}
+
+impl Camera {
+    /// Borrow the shared-memory buffers without copying.
+    ///
+    /// Like [`stream_raw`](Self::stream_raw), this requires `is_streaming` and
+    /// `is_using_shared_memory`.
+    pub fn stream_view(&self) -> Result<CameraRawView<'_>> {
+        self.check_streaming()?;
+        Ok(CameraRawView {
+            colour: self
+                .colour_shmem
+                .as_ref()
+                .map(|buffer| ShmemView { buffer }),
+            annotation: self
+                .annotation_shmem
+                .as_ref()
+                .map(|buffer| ShmemView { buffer }),
+            depth: self.depth_shmem.as_ref().map(|buffer| ShmemView { buffer }),
+        })
+    }
+
+    /// Like [`stream_raw`](Self::stream_raw), but copies into the caller's buffers so no
+    /// memory is allocated once `out` has been filled the first time.
+    pub fn stream_raw_into(&self, out: &mut CameraRawReadings) -> Result<()> {
+        self.check_streaming()?;
+        self.read_shmem_into(out);
+        Ok(())
+    }
+
+    /// Like [`poll_raw`](Self::poll_raw), but copies into the caller's buffers when using
+    /// shared memory so no memory is allocated once `out` has been filled the first time.
+    pub async fn poll_raw_into(&self, bng: &mut BeamNg, out: &mut CameraRawReadings) -> Result<()> {
+        if !self.config.is_using_shared_memory {
+            *out = self.poll_raw(bng).await?;
+            return Ok(());
+        }
+        self.send_poll_request(bng).await?;
+        self.read_shmem_into(out);
+        Ok(())
+    }
+
+    fn read_shmem_into(&self, out: &mut CameraRawReadings) {
+        read_slot(self.colour_shmem.as_ref(), &mut out.colour);
+        read_slot(self.annotation_shmem.as_ref(), &mut out.annotation);
+        read_slot(self.depth_shmem.as_ref(), &mut out.depth);
+        out.source = BufferSource::SharedMemory;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::copy_shared;
+
+    #[test]
+    fn test_copy_shared() {
+        let src: Vec<u8> = (0..=255).cycle().take(1003).collect();
+        let mut buf = vec![7; 4096];
+        // Aligned and unaligned sources, with lengths that are not a multiple of a word.
+        for offset in [0, 1, 3] {
+            let len = src.len() - offset;
+            unsafe { copy_shared(src.as_ptr().add(offset), len, &mut buf) };
+            assert_eq!(buf, src[offset..]);
+        }
+        unsafe { copy_shared(src.as_ptr(), 0, &mut buf) };
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+
+use beamng_proto::types::StrDict;
+use beamng_proto::{BngError, Result};
+use futures_util::future::BoxFuture;
+
+use crate::beamng::BeamNg;
+use crate::vehicle::Vehicle;
+
+/// Common interface of sensors that are opened, polled and closed through the
+/// game engine connection, such as [`Camera`](super::Camera) and [`Gps`](super::Gps).
+///
+/// A poll is split into [`poll_request`](Self::poll_request) and
+/// [`decode_poll`](Self::decode_poll) so that several sensors can share one round
+/// trip, see [`SensorGroup`].
+///
+/// The futures returned are `Send`, so sensors can be driven from spawned tasks.
+pub trait GeSensor: Sized + Send + Sync {
+    /// The configuration the sensor is opened with.
+    type Config;
+    /// The result of one poll.
+    type Reading: Send;
+
+    /// Open the sensor in the simulator, attached to `vehicle` if given.
+    ///
+    /// Sensors that must be attached to a vehicle fail with
+    /// [`BngError::ValueError`] when `vehicle` is `None`. Named differently from the
+    /// sensors' inherent `open` so that neither shadows the other.
+    fn open_sensor(
+        name: impl Into<String> + Send,
+        bng: &mut BeamNg,
+        vehicle: Option<&Vehicle>,
+        config: Self::Config,
+    ) -> impl Future<Output = Result<Self>> + Send;
+
+    /// The unique name of the sensor.
+    fn name(&self) -> &str;
+
+    /// The request type and fields that poll this sensor.
+    fn poll_request(&self) -> (&'static str, Vec<(&str, rmpv::Value)>);
+
+    /// Build a reading from the response to [`poll_request`](Self::poll_request).
+    fn decode_poll(&self, resp: &StrDict) -> Self::Reading;
+
+    /// Poll the sensor for its readings.
+    fn poll(&self, bng: &mut BeamNg) -> impl Future<Output = Result<Self::Reading>> + Send {
+        async move {
+            let (req_type, fields) = self.poll_request();
+            let resp = bng.conn()?.request(req_type, &fields).await?;
+            Ok(self.decode_poll(&resp))
+        }
+    }
+
+    /// Close the sensor in the simulator.
+    fn close(self, bng: &mut BeamNg) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// Fail with a [`BngError::ValueError`] unless a vehicle was given.
+pub(crate) fn require_vehicle<'v>(vehicle: Option<&'v Vehicle>, kind: &str) -> Result<&'v Vehicle> {
+    vehicle.ok_or_else(|| BngError::ValueError(format!("A {kind} must be attached to a vehicle")))
+}
+
+/// Object-safe view of a [`GeSensor`], used to store different sensor types together.
+trait AnyGeSensor: Send + Sync {
+    fn name(&self) -> &str;
+    fn poll_request(&self) -> (&'static str, Vec<(&str, rmpv::Value)>);
+    fn decode_poll(&self, resp: &StrDict) -> Box<dyn Any + Send>;
+    fn as_any(&self) -> &dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+    fn close<'a>(self: Box<Self>, bng: &'a mut BeamNg) -> BoxFuture<'a, Result<()>>;
+}
+
+impl<S> AnyGeSensor for S
+where
+    S: GeSensor + 'static,
+    S::Reading: 'static,
+{
+    fn name(&self) -> &str {
+        GeSensor::name(self)
+    }
+
+    fn poll_request(&self) -> (&'static str, Vec<(&str, rmpv::Value)>) {
+        GeSensor::poll_request(self)
+    }
+
+    fn decode_poll(&self, resp: &StrDict) -> Box<dyn Any + Send> {
+        Box::new(GeSensor::decode_poll(self, resp))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn close<'a>(self: Box<Self>, bng: &'a mut BeamNg) -> BoxFuture<'a, Result<()>> {
+        Box::pin(GeSensor::close(*self, bng))
+    }
+}
+
+/// A collection of sensors of different types that are polled together.
+///
+/// [`poll`](Self::poll) sends the requests of all sensors before waiting for any
+/// response, so the group costs about one round trip regardless of its size.
+///
+/// # Example
+/// ```no_run
+/// # async fn example(bng: &mut beamng_rs::BeamNg, vehicle: &beamng_rs::vehicle::Vehicle) -> beamng_proto::Result<()> {
+/// use beamng_rs::sensors::{AdvancedImu, Gps, SensorGroup};
+///
+/// let mut group = SensorGroup::new();
+/// group.push(Gps::open("gps", bng, vehicle, Default::default()).await?);
+/// group.push(AdvancedImu::open("imu", bng, vehicle, Default::default()).await?);
+///
+/// let readings = group.poll(bng).await?;
+/// let fixes = readings.get::<Gps>("gps").unwrap();
+/// group.close(bng).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct SensorGroup {
+    sensors: Vec<Box<dyn AnyGeSensor>>,
+}
+
+impl SensorGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an open sensor to the group.
+    pub fn push<S>(&mut self, sensor: S)
+    where
+        S: GeSensor + 'static,
+        S::Reading: 'static,
+    {
+        self.sensors.push(Box::new(sensor));
+    }
+
+    pub fn len(&self) -> usize {
+        self.sensors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sensors.is_empty()
+    }
+
+    /// The names of the sensors, in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.sensors.iter().map(|s| s.name())
+    }
+
+    /// The sensor with the given name, if it is in the group and of type `S`.
+    pub fn get<S: GeSensor + 'static>(&self, name: &str) -> Option<&S> {
+        self.sensors
+            .iter()
+            .find(|s| s.name() == name)
+            .and_then(|s| s.as_any().downcast_ref())
+    }
+
+    /// Remove and return the sensor with the given name, if it is in the group and
+    /// of type `S`. The sensor stays open.
+    pub fn remove<S: GeSensor + 'static>(&mut self, name: &str) -> Option<S> {
+        let i = self
+            .sensors
+            .iter()
+            .position(|s| s.name() == name && s.as_any().is::<S>())?;
+        self.sensors
+            .remove(i)
+            .into_any()
+            .downcast()
+            .ok()
+            .map(|s| *s)
+    }
+
+    /// Poll every sensor in the group with pipelined requests.
+    ///
+    /// If a request fails, the responses to the others already sent are still read,
+    /// so none is left behind on the connection, and the first error is returned.
+    pub async fn poll(&self, bng: &mut BeamNg) -> Result<GroupReadings> {
+        let conn = bng.conn()?;
+        let mut result = Ok(());
+        let mut request_ids = Vec::with_capacity(self.sensors.len());
+        for sensor in &self.sensors {
+            let (req_type, fields) = sensor.poll_request();
+            match conn.send_raw(req_type, &fields).await {
+                Ok(request_id) => request_ids.push(request_id),
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        let mut readings = HashMap::with_capacity(self.sensors.len());
+        for (sensor, request_id) in self.sensors.iter().zip(request_ids) {
+            match conn.recv(request_id).await {
+                Ok(resp) if result.is_ok() => {
+                    readings.insert(sensor.name().to_string(), sensor.decode_poll(&resp));
+                }
+                Ok(_) => {}
+                Err(e @ (BngError::Io(_) | BngError::Disconnected(_))) => return Err(e),
+                Err(e) => {
+                    if result.is_ok() {
+                        result = Err(e);
+                    }
+                }
+            }
+        }
+        result.map(|()| GroupReadings { readings })
+    }
+
+    /// Close every sensor in the group, returning the first error after trying all.
+    pub async fn close(self, bng: &mut BeamNg) -> Result<()> {
+        let mut result = Ok(());
+        for sensor in self.sensors {
+            let closed = sensor.close(bng).await;
+            if result.is_ok() {
+                result = closed;
+            }
+        }
+        result
+    }
+}
+
+/// The readings of one [`SensorGroup::poll`], keyed by sensor name.
+#[derive(Default)]
+pub struct GroupReadings {
+    readings: HashMap<String, Box<dyn Any + Send>>,
+}
+
+impl GroupReadings {
+    /// The reading of the named sensor, if it was polled and is of type `S`.
+    pub fn get<S: GeSensor>(&self, name: &str) -> Option<&S::Reading>
+    where
+        S::Reading: 'static,
+    {
+        self.readings.get(name)?.downcast_ref()
+    }
+
+    /// Remove and return the reading of the named sensor.
+    pub fn take<S: GeSensor>(&mut self, name: &str) -> Option<S::Reading>
+    where
+        S::Reading: 'static,
+    {
+        let reading = self.readings.remove(name)?;
+        match reading.downcast() {
+            Ok(reading) => Some(*reading),
+            Err(reading) => {
+                self.readings.insert(name.to_string(), reading);
+                None
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.readings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.readings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{mock_sim, req_type};
+
+    /// A sensor whose reading is the `value` field of its poll response.
+    struct Probe {
+        name: String,
+    }
+
+    impl GeSensor for Probe {
+        type Config = ();
+        type Reading = Option<u64>;
+
+        async fn open_sensor(
+            name: impl Into<String> + Send,
+            _bng: &mut BeamNg,
+            _vehicle: Option<&Vehicle>,
+            _config: (),
+        ) -> Result<Self> {
+            Ok(Self { name: name.into() })
+        }
+
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn poll_request(&self) -> (&'static str, Vec<(&str, rmpv::Value)>) {
+            (
+                "PollProbe",
+                vec![("name", rmpv::Value::from(self.name.as_str()))],
+            )
+        }
+
+        fn decode_poll(&self, resp: &StrDict) -> Option<u64> {
+            resp.get("value").and_then(|v| v.as_u64())
+        }
+
+        async fn close(self, _bng: &mut BeamNg) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[tokio::test]
+    async fn test_group_poll_drains_after_error() {
+        // The first poll of "b" fails; every other response carries the poll count.
+        let mut polls = 0;
+        let (port, sim) = mock_sim(move |req| {
+            assert_eq!(req_type(req), "PollProbe");
+            polls += 1;
+            if polls == 2 {
+                vec![("bngError", rmpv::Value::from("no data"))]
+            } else {
+                vec![("value", rmpv::Value::from(polls))]
+            }
+        })
+        .await;
+        let mut bng = BeamNg::new("127.0.0.1", port).connect().await.unwrap();
+        let mut group = SensorGroup::new();
+        for name in ["a", "b", "c"] {
+            group.push(Probe::open_sensor(name, &mut bng, None, ()).await.unwrap());
+        }
+
+        let poll = group.poll(&mut bng);
+        assert_send(&poll);
+        assert!(matches!(poll.await, Err(BngError::SimulatorError(_))));
+
+        // The response to "c" was consumed, so the next poll reads fresh values.
+        let readings = group.poll(&mut bng).await.unwrap();
+        let values: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .map(|n| *readings.get::<Probe>(n).unwrap())
+            .collect();
+        assert_eq!(values, vec![Some(4), Some(5), Some(6)]);
+
+        group.close(&mut bng).await.unwrap();
+        bng.disconnect();
+        assert_eq!(sim.await.unwrap().len(), 6);
+    }
+}
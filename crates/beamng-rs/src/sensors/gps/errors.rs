@@ -0,0 +1,169 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Distribution, Normal};
+
+use super::GpsReading;
+
+/// Parameters of a [`GnssErrorModel`].
+#[derive(Debug, Clone)]
+pub struct GnssErrorConfig {
+    /// Standard deviation of white position noise per horizontal axis, in metres.
+    pub noise_std: f64,
+    /// Random-walk rate of the position bias per horizontal axis, in metres per √s.
+    pub bias_drift_std: f64,
+    /// Expected number of outages per second of simulation time.
+    pub outage_rate: f64,
+    /// Length of each outage in seconds. Readings during an outage are dropped.
+    pub outage_duration: f64,
+    /// Seed of the random number generator; equal seeds give equal errors.
+    pub seed: u64,
+}
+
+impl Default for GnssErrorConfig {
+    fn default() -> Self {
+        Self {
+            noise_std: 1.5,
+            bias_drift_std: 0.05,
+            outage_rate: 0.0,
+            outage_duration: 5.0,
+            seed: 0,
+        }
+    }
+}
+
+/// Applies GNSS-like errors to ground-truth [`GpsReading`]s.
+///
+/// Each reading gets white noise plus a slowly drifting bias on `x`/`y`, and `lon`/`lat`
+/// are shifted by the same offset. Outages start at random with
+/// [`outage_rate`](GnssErrorConfig::outage_rate) and drop every reading until they end.
+/// The model is stateful, so feed it readings in time order.
+///
+/// # Example
+/// ```no_run
+/// # async fn example(bng: &mut beamng_rs::BeamNg, gps: &beamng_rs::sensors::Gps) -> beamng_proto::Result<()> {
+/// use beamng_rs::sensors::{GnssErrorConfig, GnssErrorModel};
+///
+/// let mut errors = GnssErrorModel::new(GnssErrorConfig { seed: 42, ..Default::default() });
+/// let noisy = errors.apply(gps.poll(bng).await?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GnssErrorModel {
+    config: GnssErrorConfig,
+    rng: ChaCha8Rng,
+    bias: (f64, f64),
+    last_time: Option<f64>,
+    outage_until: Option<f64>,
+}
+
+impl GnssErrorModel {
+    pub fn new(config: GnssErrorConfig) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(config.seed),
+            config,
+            bias: (0.0, 0.0),
+            last_time: None,
+            outage_until: None,
+        }
+    }
+
+    pub fn config(&self) -> &GnssErrorConfig {
+        &self.config
+    }
+
+    /// The current position bias `(east, north)` in metres.
+    pub fn bias(&self) -> (f64, f64) {
+        self.bias
+    }
+
+    /// Whether an outage is in progress at the time of the last reading.
+    pub fn in_outage(&self) -> bool {
+        match (self.outage_until, self.last_time) {
+            (Some(until), Some(t)) => t < until,
+            _ => false,
+        }
+    }
+
+    fn gaussian(&mut self, std: f64) -> f64 {
+        if std > 0.0 {
+            Normal::new(0.0, std)
+                .map(|n| n.sample(&mut self.rng))
+                .unwrap_or(0.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Apply the error model to one reading, or return `None` if it falls in an outage.
+    pub fn apply_one(&mut self, reading: &GpsReading) -> Option<GpsReading> {
+        let dt = self
+            .last_time
+            .map_or(0.0, |last| (reading.time - last).max(0.0));
+        self.last_time = Some(reading.time);
+
+        let drift = self.config.bias_drift_std * dt.sqrt();
+        self.bias.0 += self.gaussian(drift);
+        self.bias.1 += self.gaussian(drift);
+
+        if let Some(until) = self.outage_until {
+            if reading.time < until {
+                return None;
+            }
+            self.outage_until = None;
+        }
+        if self.config.outage_rate > 0.0 && dt > 0.0 {
+            let p = 1.0 - (-self.config.outage_rate * dt).exp();
+            if self.rng.gen::<f64>() < p {
+                self.outage_until = Some(reading.time + self.config.outage_duration);
+                return None;
+            }
+        }
+
+        let east = self.bias.0 + self.gaussian(self.config.noise_std);
+        let north = self.bias.1 + self.gaussian(self.config.noise_std);
+        Some(reading.shifted(east, north))
+    }
+
+    /// Apply the error model to a batch of readings, such as the result of
+    /// [`Gps::poll`](super::Gps::poll), dropping those that fall in an outage.
+    pub fn apply(&mut self, readings: Vec<GpsReading>) -> Vec<GpsReading> {
+        readings.iter().filter_map(|r| self.apply_one(r)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::gps::geodesy::{EnuFrame, Geodetic};
+
+    #[test]
+    fn test_deterministic_errors_and_outages() {
+        let readings: Vec<GpsReading> = (0..200)
+            .map(|i| GpsReading {
+                time: i as f64 * 0.1,
+                lat: 45.0,
+                lon: 7.0,
+                ..Default::default()
+            })
+            .collect();
+        let config = GnssErrorConfig {
+            outage_rate: 0.2,
+            outage_duration: 1.0,
+            seed: 7,
+            ..Default::default()
+        };
+
+        let a = GnssErrorModel::new(config.clone()).apply(readings.clone());
+        let b = GnssErrorModel::new(config).apply(readings.clone());
+        assert_eq!(a.len(), b.len());
+        assert!(a.iter().zip(&b).all(|(a, b)| a.x == b.x && a.lat == b.lat));
+        assert!(a.len() < readings.len(), "expected some outages");
+
+        // The lon/lat shift matches the metric one.
+        let r = &a[0];
+        let frame = EnuFrame::new(Geodetic::new(45.0, 7.0, 0.0));
+        let enu = frame.geodetic_to_enu(Geodetic::new(r.lat, r.lon, 0.0));
+        assert!((enu.0 - r.x).abs() < 1e-6 && (enu.1 - r.y).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,289 @@
+use beamng_proto::types::Vec3;
+use beamng_proto::{BngError, Result};
+
+/// WGS84 semi-major axis in metres.
+pub const WGS84_A: f64 = 6_378_137.0;
+/// WGS84 flattening.
+pub const WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// WGS84 first eccentricity squared.
+const WGS84_E2: f64 = WGS84_F * (2.0 - WGS84_F);
+/// UTM scale factor on the central meridian.
+const UTM_K0: f64 = 0.9996;
+
+/// A WGS84 geodetic position.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Geodetic {
+    /// Latitude in degrees, positive north.
+    pub lat: f64,
+    /// Longitude in degrees, positive east.
+    pub lon: f64,
+    /// Height above the ellipsoid in metres.
+    pub alt: f64,
+}
+
+impl Geodetic {
+    pub fn new(lat: f64, lon: f64, alt: f64) -> Self {
+        Self { lat, lon, alt }
+    }
+
+    /// Convert to Earth-centred, Earth-fixed coordinates in metres.
+    pub fn to_ecef(&self) -> Vec3 {
+        let (lat, lon) = (self.lat.to_radians(), self.lon.to_radians());
+        let n = WGS84_A / (1.0 - WGS84_E2 * lat.sin().powi(2)).sqrt();
+        (
+            (n + self.alt) * lat.cos() * lon.cos(),
+            (n + self.alt) * lat.cos() * lon.sin(),
+            (n * (1.0 - WGS84_E2) + self.alt) * lat.sin(),
+        )
+    }
+
+    /// Convert from Earth-centred, Earth-fixed coordinates in metres.
+    pub fn from_ecef(ecef: Vec3) -> Self {
+        let (x, y, z) = ecef;
+        let p = (x * x + y * y).sqrt();
+        let lon = y.atan2(x);
+        let mut lat = z.atan2(p * (1.0 - WGS84_E2));
+        let mut alt = 0.0;
+        for _ in 0..6 {
+            let n = WGS84_A / (1.0 - WGS84_E2 * lat.sin().powi(2)).sqrt();
+            alt = if lat.cos().abs() > 1e-10 {
+                p / lat.cos() - n
+            } else {
+                z.abs() - n * (1.0 - WGS84_E2)
+            };
+            lat = z.atan2(p * (1.0 - WGS84_E2 * n / (n + alt)));
+        }
+        Self {
+            lat: lat.to_degrees(),
+            lon: lon.to_degrees(),
+            alt,
+        }
+    }
+
+    /// Project onto the UTM grid, using the standard zone for the longitude.
+    pub fn to_utm(&self) -> Result<Utm> {
+        let zone = ((self.lon + 180.0) / 6.0).floor().clamp(0.0, 59.0) as u8 + 1;
+        self.to_utm_zone(zone)
+    }
+
+    /// Project onto the UTM grid of a given zone (1 to 60).
+    ///
+    /// Accuracy degrades away from the zone's central meridian, so forcing a zone is
+    /// only meaningful for points in or next to it.
+    pub fn to_utm_zone(&self, zone: u8) -> Result<Utm> {
+        if !(1..=60).contains(&zone) {
+            return Err(BngError::ValueError(format!(
+                "UTM zone must be in 1..=60, got {zone}"
+            )));
+        }
+        if !(-80.0..=84.0).contains(&self.lat) {
+            return Err(BngError::ValueError(format!(
+                "UTM is only defined between 80°S and 84°N, got {}",
+                self.lat
+            )));
+        }
+        let e2 = WGS84_E2;
+        let ep2 = e2 / (1.0 - e2);
+        let lat = self.lat.to_radians();
+        let lon0 = utm_central_meridian(zone).to_radians();
+
+        let n = WGS84_A / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+        let t = lat.tan().powi(2);
+        let c = ep2 * lat.cos().powi(2);
+        let a = lat.cos() * (self.lon.to_radians() - lon0);
+        let m = meridian_arc(lat);
+
+        let easting = UTM_K0
+            * n
+            * (a + (1.0 - t + c) * a.powi(3) / 6.0
+                + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a.powi(5) / 120.0)
+            + 500_000.0;
+        let mut northing = UTM_K0
+            * (m + n
+                * lat.tan()
+                * (a * a / 2.0
+                    + (5.0 - t + 9.0 * c + 4.0 * c * c) * a.powi(4) / 24.0
+                    + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a.powi(6) / 720.0));
+        let northern = self.lat >= 0.0;
+        if !northern {
+            northing += 10_000_000.0;
+        }
+        Ok(Utm {
+            zone,
+            northern,
+            easting,
+            northing,
+        })
+    }
+}
+
+fn utm_central_meridian(zone: u8) -> f64 {
+    (zone as f64 - 1.0) * 6.0 - 180.0 + 3.0
+}
+
+/// Distance along the meridian from the equator to latitude `lat` (radians).
+fn meridian_arc(lat: f64) -> f64 {
+    let e2 = WGS84_E2;
+    let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+    WGS84_A
+        * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * lat
+            - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * lat).sin()
+            + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * lat).sin()
+            - (35.0 * e6 / 3072.0) * (6.0 * lat).sin())
+}
+
+/// A position on the Universal Transverse Mercator grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Utm {
+    /// Zone number, 1 to 60.
+    pub zone: u8,
+    /// Whether the position is in the northern hemisphere.
+    pub northern: bool,
+    /// Easting in metres, including the 500 km false easting.
+    pub easting: f64,
+    /// Northing in metres, including the 10000 km false northing in the south.
+    pub northing: f64,
+}
+
+impl Utm {
+    /// Convert back to a geodetic position at zero altitude.
+    pub fn to_geodetic(&self) -> Geodetic {
+        let e2 = WGS84_E2;
+        let ep2 = e2 / (1.0 - e2);
+        let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+        let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+
+        let y = if self.northern {
+            self.northing
+        } else {
+            self.northing - 10_000_000.0
+        };
+        let m = y / UTM_K0;
+        let mu = m / (WGS84_A * (1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0));
+        let lat1 = mu
+            + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+            + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+            + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+            + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+        let sin2 = lat1.sin().powi(2);
+        let n1 = WGS84_A / (1.0 - e2 * sin2).sqrt();
+        let t1 = lat1.tan().powi(2);
+        let c1 = ep2 * lat1.cos().powi(2);
+        let r1 = WGS84_A * (1.0 - e2) / (1.0 - e2 * sin2).powf(1.5);
+        let d = (self.easting - 500_000.0) / (n1 * UTM_K0);
+
+        let lat = lat1
+            - (n1 * lat1.tan() / r1)
+                * (d * d / 2.0
+                    - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1 * c1 - 9.0 * ep2) * d.powi(4) / 24.0
+                    + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1 * t1
+                        - 252.0 * ep2
+                        - 3.0 * c1 * c1)
+                        * d.powi(6)
+                        / 720.0);
+        let lon = (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+            + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1 * c1 + 8.0 * ep2 + 24.0 * t1 * t1)
+                * d.powi(5)
+                / 120.0)
+            / lat1.cos();
+
+        Geodetic {
+            lat: lat.to_degrees(),
+            lon: utm_central_meridian(self.zone) + lon.to_degrees(),
+            alt: 0.0,
+        }
+    }
+}
+
+/// A local East-North-Up tangent plane anchored at a geodetic origin.
+///
+/// BeamNG's world axes are `x` east, `y` north and `z` up, so simulator positions
+/// around a GPS reference point can be treated as ENU coordinates in this frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnuFrame {
+    origin: Geodetic,
+    origin_ecef: Vec3,
+}
+
+impl EnuFrame {
+    pub fn new(origin: Geodetic) -> Self {
+        Self {
+            origin,
+            origin_ecef: origin.to_ecef(),
+        }
+    }
+
+    pub fn origin(&self) -> Geodetic {
+        self.origin
+    }
+
+    pub fn ecef_to_enu(&self, ecef: Vec3) -> Vec3 {
+        let (lat, lon) = (self.origin.lat.to_radians(), self.origin.lon.to_radians());
+        let (dx, dy, dz) = (
+            ecef.0 - self.origin_ecef.0,
+            ecef.1 - self.origin_ecef.1,
+            ecef.2 - self.origin_ecef.2,
+        );
+        (
+            -lon.sin() * dx + lon.cos() * dy,
+            -lat.sin() * lon.cos() * dx - lat.sin() * lon.sin() * dy + lat.cos() * dz,
+            lat.cos() * lon.cos() * dx + lat.cos() * lon.sin() * dy + lat.sin() * dz,
+        )
+    }
+
+    pub fn enu_to_ecef(&self, enu: Vec3) -> Vec3 {
+        let (lat, lon) = (self.origin.lat.to_radians(), self.origin.lon.to_radians());
+        let (e, n, u) = enu;
+        (
+            self.origin_ecef.0 - lon.sin() * e - lat.sin() * lon.cos() * n
+                + lat.cos() * lon.cos() * u,
+            self.origin_ecef.1 + lon.cos() * e - lat.sin() * lon.sin() * n
+                + lat.cos() * lon.sin() * u,
+            self.origin_ecef.2 + lat.cos() * n + lat.sin() * u,
+        )
+    }
+
+    pub fn geodetic_to_enu(&self, geodetic: Geodetic) -> Vec3 {
+        self.ecef_to_enu(geodetic.to_ecef())
+    }
+
+    pub fn enu_to_geodetic(&self, enu: Vec3) -> Geodetic {
+        Geodetic::from_ecef(self.enu_to_ecef(enu))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ecef_enu_utm_roundtrips() {
+        let origin = Geodetic::new(48.8583, 2.2945, 35.0);
+        let back = Geodetic::from_ecef(origin.to_ecef());
+        assert!((back.lat - origin.lat).abs() < 1e-9 && (back.lon - origin.lon).abs() < 1e-9);
+        assert!((back.alt - origin.alt).abs() < 1e-6);
+
+        let frame = EnuFrame::new(origin);
+        let enu = (120.0, -340.0, 5.0);
+        let p = frame.enu_to_geodetic(enu);
+        let again = frame.geodetic_to_enu(p);
+        assert!((again.0 - enu.0).abs() < 1e-6 && (again.1 - enu.1).abs() < 1e-6);
+        assert!((again.2 - enu.2).abs() < 1e-6);
+        // 340 m south is roughly 0.00306 degrees of latitude.
+        assert!((origin.lat - p.lat - 0.003_057).abs() < 1e-5);
+
+        // Eiffel Tower: 31U 448252 5411944.
+        let utm = origin.to_utm().unwrap();
+        assert_eq!((utm.zone, utm.northern), (31, true));
+        assert!((utm.easting - 448_251.9).abs() < 0.5);
+        assert!((utm.northing - 5_411_943.8).abs() < 0.5);
+        let g = utm.to_geodetic();
+        assert!((g.lat - origin.lat).abs() < 1e-7 && (g.lon - origin.lon).abs() < 1e-7);
+
+        let south = Geodetic::new(-33.8568, 151.2153, 0.0).to_utm().unwrap();
+        assert_eq!((south.zone, south.northern), (56, false));
+        let g = south.to_geodetic();
+        assert!((g.lat + 33.8568).abs() < 1e-7 && (g.lon - 151.2153).abs() < 1e-7);
+    }
+}
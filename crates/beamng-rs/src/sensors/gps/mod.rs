@@ -0,0 +1,257 @@
+use beamng_proto::types::{StrDict, Vec3};
+use beamng_proto::Result;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::beamng::BeamNg;
+use crate::sensors::ge_sensor::require_vehicle;
+use crate::sensors::registry::{mark_dropped, SharedSensorRegistry};
+use crate::sensors::{fetch_sensor_id, parse_readings, GeSensor, SensorKind, VePoll};
+use crate::vehicle::Vehicle;
+
+mod errors;
+mod geodesy;
+mod nmea;
+
+pub use errors::{GnssErrorConfig, GnssErrorModel};
+pub use geodesy::{EnuFrame, Geodetic, Utm, WGS84_A, WGS84_F};
+pub use nmea::{nmea_checksum, NmeaFix, NmeaGenerator, NmeaSentences};
+
+/// Configuration for a [`Gps`] sensor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GpsConfig {
+    pub gfx_update_time: f64,
+    pub physics_update_time: f64,
+    pub pos: Vec3,
+    pub ref_lon: f64,
+    pub ref_lat: f64,
+    pub is_send_immediately: bool,
+    pub is_visualised: bool,
+    pub is_snapping_desired: bool,
+    pub is_force_inside_triangle: bool,
+    pub is_dir_world_space: bool,
+}
+
+impl Default for GpsConfig {
+    fn default() -> Self {
+        Self {
+            gfx_update_time: 0.0,
+            physics_update_time: 0.01,
+            pos: (0.0, 0.0, 1.7),
+            ref_lon: 0.0,
+            ref_lat: 0.0,
+            is_send_immediately: false,
+            is_visualised: true,
+            is_snapping_desired: false,
+            is_force_inside_triangle: false,
+            is_dir_world_space: false,
+        }
+    }
+}
+
+impl GpsConfig {
+    /// The local ENU frame anchored at the reference longitude and latitude.
+    pub fn enu_frame(&self) -> EnuFrame {
+        EnuFrame::new(Geodetic::new(self.ref_lat, self.ref_lon, 0.0))
+    }
+}
+
+/// A single GPS reading.
+#[derive(Debug, Clone, Default)]
+pub struct GpsReading {
+    pub time: f64,
+    pub x: f64,
+    pub y: f64,
+    pub lon: f64,
+    pub lat: f64,
+}
+
+impl GpsReading {
+    /// The reading's WGS84 position, at zero altitude.
+    pub fn geodetic(&self) -> Geodetic {
+        Geodetic::new(self.lat, self.lon, 0.0)
+    }
+
+    /// The reading's local position in metres. BeamNG's `x`/`y` point east and north,
+    /// so this is also its ENU position around the reference point.
+    pub fn local(&self) -> Vec3 {
+        (self.x, self.y, 0.0)
+    }
+
+    /// The reading moved by `east`/`north` metres, in both `x`/`y` and `lon`/`lat`.
+    pub(crate) fn shifted(&self, east: f64, north: f64) -> GpsReading {
+        let moved = EnuFrame::new(self.geodetic()).enu_to_geodetic((east, north, 0.0));
+        GpsReading {
+            time: self.time,
+            x: self.x + east,
+            y: self.y + north,
+            lon: moved.lon,
+            lat: moved.lat,
+        }
+    }
+}
+
+fn parse_reading(map: &beamng_proto::types::StrDict) -> GpsReading {
+    GpsReading {
+        time: map.get("time").and_then(|v| v.as_f64()).unwrap_or(0.0),
+        x: map.get("x").and_then(|v| v.as_f64()).unwrap_or(0.0),
+        y: map.get("y").and_then(|v| v.as_f64()).unwrap_or(0.0),
+        lon: map.get("lon").and_then(|v| v.as_f64()).unwrap_or(0.0),
+        lat: map.get("lat").and_then(|v| v.as_f64()).unwrap_or(0.0),
+    }
+}
+
+/// A GPS sensor attached to a vehicle (GE-level).
+pub struct Gps {
+    name: String,
+    vid: String,
+    /// The simulator-side ID, known only for sensors opened with `is_send_immediately`.
+    sensor_id: Option<u64>,
+    registry: SharedSensorRegistry,
+}
+
+impl Drop for Gps {
+    fn drop(&mut self) {
+        mark_dropped(&self.registry, &self.name);
+    }
+}
+
+impl Gps {
+    /// Open a GPS sensor in the simulator, attached to the given vehicle.
+    pub async fn open(
+        name: impl Into<String>,
+        bng: &mut BeamNg,
+        vehicle: &Vehicle,
+        config: GpsConfig,
+    ) -> Result<Self> {
+        let name = name.into();
+        bng.check_sensor_name(&name)?;
+        let vid = vehicle.vid.clone();
+
+        let fields: Vec<(&str, rmpv::Value)> = vec![
+            ("name", rmpv::Value::from(name.as_str())),
+            ("vid", rmpv::Value::from(vid.as_str())),
+            ("GFXUpdateTime", rmpv::Value::from(config.gfx_update_time)),
+            (
+                "physicsUpdateTime",
+                rmpv::Value::from(config.physics_update_time),
+            ),
+            (
+                "pos",
+                rmpv::Value::Array(vec![
+                    rmpv::Value::from(config.pos.0),
+                    rmpv::Value::from(config.pos.1),
+                    rmpv::Value::from(config.pos.2),
+                ]),
+            ),
+            ("refLon", rmpv::Value::from(config.ref_lon)),
+            ("refLat", rmpv::Value::from(config.ref_lat)),
+            (
+                "isSendImmediately",
+                rmpv::Value::from(config.is_send_immediately),
+            ),
+            ("isVisualised", rmpv::Value::from(config.is_visualised)),
+            (
+                "isSnappingDesired",
+                rmpv::Value::from(config.is_snapping_desired),
+            ),
+            (
+                "isForceInsideTriangle",
+                rmpv::Value::from(config.is_force_inside_triangle),
+            ),
+            (
+                "isDirWorldSpace",
+                rmpv::Value::from(config.is_dir_world_space),
+            ),
+        ];
+
+        bng.conn()?.ack("OpenGPS", "OpenedGPS", &fields).await?;
+
+        info!("Opened GPS: \"{}\"", name);
+        bng.register_sensor(SensorKind::Gps, &name, Some(&vid));
+
+        // The vehicle-engine poll addresses the sensor by its simulator-side ID.
+        let sensor_id = if config.is_send_immediately {
+            Some(fetch_sensor_id(bng, "GetGPSId", &name).await?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            name,
+            vid,
+            sensor_id,
+            registry: bng.shared_sensor_registry(),
+        })
+    }
+
+    /// Poll the sensor for readings through the game engine.
+    ///
+    /// Returns a list of readings accumulated since the last poll. For the lowest
+    /// latency in immediate mode use [`poll_ve`](Self::poll_ve) instead.
+    pub async fn poll(&self, bng: &mut BeamNg) -> Result<Vec<GpsReading>> {
+        GeSensor::poll(self, bng).await
+    }
+
+    /// Poll the latest reading directly from the vehicle engine.
+    ///
+    /// The request goes over the vehicle's own connection, bypassing the game engine.
+    /// Requires the sensor to have been opened with `is_send_immediately: true`
+    /// and `vehicle` to be the connected vehicle it is attached to.
+    pub async fn poll_ve(&self, vehicle: &mut Vehicle) -> Result<Vec<GpsReading>> {
+        VePoll {
+            label: "GPS",
+            name: &self.name,
+            vid: &self.vid,
+            sensor_id: self.sensor_id,
+            request: "PollGPSVE",
+        }
+        .poll(vehicle, |m| parse_reading(&m))
+        .await
+    }
+
+    /// Close the sensor.
+    pub async fn close(self, bng: &mut BeamNg) -> Result<()> {
+        bng.close_sensor(&self.name).await
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl GeSensor for Gps {
+    type Config = GpsConfig;
+    type Reading = Vec<GpsReading>;
+
+    async fn open_sensor(
+        name: impl Into<String> + Send,
+        bng: &mut BeamNg,
+        vehicle: Option<&Vehicle>,
+        config: GpsConfig,
+    ) -> Result<Self> {
+        Gps::open(name, bng, require_vehicle(vehicle, "GPS")?, config).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn poll_request(&self) -> (&'static str, Vec<(&str, rmpv::Value)>) {
+        (
+            "PollGPSGE",
+            vec![("name", rmpv::Value::from(self.name.as_str()))],
+        )
+    }
+
+    fn decode_poll(&self, resp: &StrDict) -> Vec<GpsReading> {
+        resp.get("data")
+            .map(|data| parse_readings(data, |m| parse_reading(&m)))
+            .unwrap_or_default()
+    }
+
+    async fn close(self, bng: &mut BeamNg) -> Result<()> {
+        Gps::close(self, bng).await
+    }
+}
@@ -0,0 +1,241 @@
+use std::fmt::Write;
+
+use super::GpsReading;
+
+const KNOTS_PER_MPS: f64 = 3600.0 / 1852.0;
+
+/// The XOR checksum of an NMEA sentence body (the text between `$` and `*`).
+pub fn nmea_checksum(body: &str) -> u8 {
+    body.bytes().fold(0, |acc, b| acc ^ b)
+}
+
+/// Wrap a sentence body as `$<body>*<checksum>`.
+fn sentence(body: &str) -> String {
+    format!("${body}*{:02X}", nmea_checksum(body))
+}
+
+/// Format an angle as NMEA `d..dmm.mmmmm,<hemisphere>` with `deg_digits` degree digits.
+fn angle(value: f64, deg_digits: usize, pos: char, neg: char) -> String {
+    let hemisphere = if value < 0.0 { neg } else { pos };
+    let value = value.abs();
+    let mut deg = value.trunc();
+    let mut min = (value - deg) * 60.0;
+    // Avoid printing 60.00000 minutes after rounding.
+    if min >= 59.999_995 {
+        deg += 1.0;
+        min = 0.0;
+    }
+    format!(
+        "{:0width$}{:08.5},{hemisphere}",
+        deg as u32,
+        min,
+        width = deg_digits
+    )
+}
+
+/// Format seconds since midnight as `hhmmss.ss`.
+fn time_of_day(utc_seconds: f64) -> String {
+    let centis = (utc_seconds.rem_euclid(86_400.0) * 100.0).round() as u64 % 8_640_000;
+    let (h, rest) = (centis / 360_000, centis % 360_000);
+    let (m, rest) = (rest / 6_000, rest % 6_000);
+    format!("{h:02}{m:02}{:02}.{:02}", rest / 100, rest % 100)
+}
+
+/// One GNSS fix, as reported in NMEA sentences.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NmeaFix {
+    /// UTC time of the fix in seconds since midnight.
+    pub utc_seconds: f64,
+    /// UTC date as `(day, month, year)`.
+    pub date: (u8, u8, u16),
+    pub lat: f64,
+    pub lon: f64,
+    /// Altitude above mean sea level in metres.
+    pub alt: f64,
+    /// Ground speed in metres per second, if known.
+    pub speed: Option<f64>,
+    /// Course over ground in degrees clockwise from true north, if known.
+    pub course: Option<f64>,
+    pub satellites: u8,
+    pub hdop: f64,
+    /// Whether the fix is valid. Invalid fixes are reported with quality 0 / status `V`.
+    pub valid: bool,
+}
+
+impl NmeaFix {
+    /// A `GGA` (fix data) sentence.
+    pub fn gga(&self) -> String {
+        let mut body = String::from("GPGGA,");
+        body.push_str(&time_of_day(self.utc_seconds));
+        let quality = if self.valid { 1 } else { 0 };
+        let _ = write!(
+            body,
+            ",{},{},{quality},{:02},{:.1},{:.1},M,0.0,M,,",
+            angle(self.lat, 2, 'N', 'S'),
+            angle(self.lon, 3, 'E', 'W'),
+            self.satellites,
+            self.hdop,
+            self.alt
+        );
+        sentence(&body)
+    }
+
+    /// An `RMC` (recommended minimum) sentence.
+    pub fn rmc(&self) -> String {
+        let (status, mode) = if self.valid { ('A', 'A') } else { ('V', 'N') };
+        let (day, month, year) = self.date;
+        let body = format!(
+            "GPRMC,{},{status},{},{},{},{},{day:02}{month:02}{:02},,,{mode}",
+            time_of_day(self.utc_seconds),
+            angle(self.lat, 2, 'N', 'S'),
+            angle(self.lon, 3, 'E', 'W'),
+            self.speed
+                .map(|s| format!("{:.2}", s * KNOTS_PER_MPS))
+                .unwrap_or_default(),
+            self.course.map(|c| format!("{c:.2}")).unwrap_or_default(),
+            year % 100
+        );
+        sentence(&body)
+    }
+
+    /// A `VTG` (course and ground speed) sentence.
+    pub fn vtg(&self) -> String {
+        let mode = if self.valid { 'A' } else { 'N' };
+        let knots = self
+            .speed
+            .map(|s| format!("{:.2}", s * KNOTS_PER_MPS))
+            .unwrap_or_default();
+        let kmh = self
+            .speed
+            .map(|s| format!("{:.2}", s * 3.6))
+            .unwrap_or_default();
+        let course = self.course.map(|c| format!("{c:.2}")).unwrap_or_default();
+        sentence(&format!("GPVTG,{course},T,,M,{knots},N,{kmh},K,{mode}"))
+    }
+}
+
+/// The sentences generated for one reading by [`NmeaGenerator`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NmeaSentences {
+    pub gga: String,
+    pub rmc: String,
+    pub vtg: String,
+}
+
+/// Turns a sequence of [`GpsReading`]s into NMEA 0183 sentences.
+///
+/// Speed and course are derived from the local `x`/`y` of consecutive readings, so
+/// the first reading has neither. UTC time is `utc_start` plus the reading's
+/// simulation time, wrapping at midnight without advancing the date.
+///
+/// # Example
+/// ```
+/// use beamng_rs::sensors::{GpsReading, NmeaGenerator};
+///
+/// let mut nmea = NmeaGenerator::new();
+/// let out = nmea.sentences(&GpsReading { time: 1.0, lat: 48.1, lon: 11.5, ..Default::default() });
+/// assert!(out.gga.starts_with("$GPGGA,000001.00,4806.00000,N,01130.00000,E"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct NmeaGenerator {
+    /// UTC seconds since midnight at simulation time 0.
+    pub utc_start: f64,
+    /// UTC date as `(day, month, year)`.
+    pub date: (u8, u8, u16),
+    /// Altitude reported in `GGA`, in metres.
+    pub altitude: f64,
+    pub satellites: u8,
+    pub hdop: f64,
+    previous: Option<GpsReading>,
+}
+
+impl Default for NmeaGenerator {
+    fn default() -> Self {
+        Self {
+            utc_start: 0.0,
+            date: (1, 1, 2000),
+            altitude: 0.0,
+            satellites: 12,
+            hdop: 0.8,
+            previous: None,
+        }
+    }
+}
+
+impl NmeaGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the fix for a reading and remember it for the next speed estimate.
+    pub fn fix(&mut self, reading: &GpsReading) -> NmeaFix {
+        let motion = self.previous.as_ref().and_then(|prev| {
+            let dt = reading.time - prev.time;
+            if dt <= 0.0 {
+                return None;
+            }
+            let (de, dn) = (reading.x - prev.x, reading.y - prev.y);
+            let speed = (de * de + dn * dn).sqrt() / dt;
+            let course = de.atan2(dn).to_degrees().rem_euclid(360.0);
+            Some((speed, course))
+        });
+        self.previous = Some(reading.clone());
+
+        NmeaFix {
+            utc_seconds: self.utc_start + reading.time,
+            date: self.date,
+            lat: reading.lat,
+            lon: reading.lon,
+            alt: self.altitude,
+            speed: motion.map(|m| m.0),
+            course: motion.map(|m| m.1),
+            satellites: self.satellites,
+            hdop: self.hdop,
+            valid: true,
+        }
+    }
+
+    /// Generate the `GGA`, `RMC` and `VTG` sentences for a reading.
+    pub fn sentences(&mut self, reading: &GpsReading) -> NmeaSentences {
+        let fix = self.fix(reading);
+        NmeaSentences {
+            gga: fix.gga(),
+            rmc: fix.rmc(),
+            vtg: fix.vtg(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentences() {
+        assert_eq!(
+            nmea_checksum("GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,"),
+            0x47
+        );
+        assert_eq!(angle(-0.5, 3, 'E', 'W'), "00030.00000,W");
+        assert_eq!(time_of_day(86_400.0 + 3_723.5), "010203.50");
+
+        let mut nmea = NmeaGenerator::new();
+        let first = nmea.sentences(&GpsReading {
+            time: 0.0,
+            ..Default::default()
+        });
+        assert!(first.vtg.starts_with("$GPVTG,,T,,M,,N,,K,A*"));
+        let fix = nmea.fix(&GpsReading {
+            time: 2.0,
+            x: 10.0,
+            y: 0.0,
+            ..Default::default()
+        });
+        assert_eq!(fix.speed, Some(5.0));
+        assert_eq!(fix.course, Some(90.0));
+        let rmc = fix.rmc();
+        let (body, cs) = rmc[1..].split_once('*').unwrap();
+        assert_eq!(u8::from_str_radix(cs, 16).unwrap(), nmea_checksum(body));
+        assert!(body.contains(",9.72,90.00,010100,"));
+    }
+}
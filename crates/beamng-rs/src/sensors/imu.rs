@@ -1,12 +1,17 @@
-use beamng_proto::types::Vec3;
+use beamng_proto::types::{StrDict, Vec3};
 use beamng_proto::Result;
+use serde::{Deserialize, Serialize};
 use tracing::info;
 
 use crate::beamng::BeamNg;
+use crate::sensors::ge_sensor::require_vehicle;
+use crate::sensors::registry::{mark_dropped, SharedSensorRegistry};
+use crate::sensors::{fetch_sensor_id, parse_readings, GeSensor, SensorKind, VePoll};
 use crate::vehicle::Vehicle;
 
 /// Configuration for an [`AdvancedImu`] sensor.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct AdvancedImuConfig {
     pub gfx_update_time: f64,
     pub physics_update_time: f64,
@@ -89,39 +94,19 @@ fn parse_reading(map: &beamng_proto::types::StrDict) -> ImuReading {
     }
 }
 
-/// Parse a list of readings from a response value.
-///
-/// The simulator returns a Map with numeric F64 keys (0.0, 1.0, 2.0, ...)
-/// where each value is a reading map with string keys.
-fn parse_readings(val: &rmpv::Value) -> Vec<ImuReading> {
-    match val {
-        rmpv::Value::Array(arr) => arr
-            .iter()
-            .filter_map(|v| {
-                beamng_proto::types::value_to_str_dict(v.clone()).map(|m| parse_reading(&m))
-            })
-            .collect(),
-        rmpv::Value::Map(pairs) => {
-            let mut readings: Vec<(f64, ImuReading)> = pairs
-                .iter()
-                .filter_map(|(k, v)| {
-                    let idx = k.as_f64().or_else(|| k.as_u64().map(|i| i as f64))?;
-                    let map = beamng_proto::types::value_to_str_dict(v.clone())?;
-                    Some((idx, parse_reading(&map)))
-                })
-                .collect();
-            readings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
-            readings.into_iter().map(|(_, r)| r).collect()
-        }
-        _ => vec![],
-    }
-}
-
 /// An Advanced IMU sensor attached to a vehicle (GE-level).
 pub struct AdvancedImu {
     name: String,
     vid: String,
-    is_send_immediately: bool,
+    /// The simulator-side ID, known only for sensors opened with `is_send_immediately`.
+    sensor_id: Option<u64>,
+    registry: SharedSensorRegistry,
+}
+
+impl Drop for AdvancedImu {
+    fn drop(&mut self) {
+        mark_dropped(&self.registry, &self.name);
+    }
 }
 
 impl AdvancedImu {
@@ -133,6 +118,7 @@ impl AdvancedImu {
         config: AdvancedImuConfig,
     ) -> Result<Self> {
         let name = name.into();
+        bng.check_sensor_name(&name)?;
         let vid = vehicle.vid.clone();
 
         let fields: Vec<(&str, rmpv::Value)> = vec![
@@ -200,59 +186,90 @@ impl AdvancedImu {
             .await?;
 
         info!("Opened AdvancedIMU: \"{}\"", name);
+        bng.register_sensor(SensorKind::AdvancedImu, &name, Some(&vid));
+
+        // The vehicle-engine poll addresses the sensor by its simulator-side ID.
+        let sensor_id = if config.is_send_immediately {
+            Some(fetch_sensor_id(bng, "GetAdvancedImuId", &name).await?)
+        } else {
+            None
+        };
 
         Ok(Self {
             name,
             vid,
-            is_send_immediately: config.is_send_immediately,
+            sensor_id,
+            registry: bng.shared_sensor_registry(),
         })
     }
 
-    /// Poll the sensor for readings.
+    /// Poll the sensor for readings through the game engine.
     ///
-    /// Returns a list of readings accumulated since the last poll (bulk mode)
-    /// or the single latest reading (immediate mode).
+    /// Returns a list of readings accumulated since the last poll. Works in both
+    /// bulk and immediate mode; for the lowest latency in immediate mode use
+    /// [`poll_ve`](Self::poll_ve) instead.
     pub async fn poll(&self, bng: &mut BeamNg) -> Result<Vec<ImuReading>> {
-        let resp = if self.is_send_immediately {
-            // VE poll would require the sensorId and vehicle connection.
-            // For simplicity, use GE poll which works for both modes.
-            bng.conn()?
-                .request(
-                    "PollAdvancedImuGE",
-                    &[("name", rmpv::Value::from(self.name.as_str()))],
-                )
-                .await?
-        } else {
-            bng.conn()?
-                .request(
-                    "PollAdvancedImuGE",
-                    &[("name", rmpv::Value::from(self.name.as_str()))],
-                )
-                .await?
-        };
-
-        let readings = resp.get("data").map(parse_readings).unwrap_or_default();
+        GeSensor::poll(self, bng).await
+    }
 
-        Ok(readings)
+    /// Poll the latest reading directly from the vehicle engine.
+    ///
+    /// The request goes over the vehicle's own connection, bypassing the game engine.
+    /// Requires the sensor to have been opened with `is_send_immediately: true`
+    /// and `vehicle` to be the connected vehicle it is attached to.
+    pub async fn poll_ve(&self, vehicle: &mut Vehicle) -> Result<Vec<ImuReading>> {
+        VePoll {
+            label: "IMU",
+            name: &self.name,
+            vid: &self.vid,
+            sensor_id: self.sensor_id,
+            request: "PollAdvancedImuVE",
+        }
+        .poll(vehicle, |m| parse_reading(&m))
+        .await
     }
 
     /// Close the sensor.
     pub async fn close(self, bng: &mut BeamNg) -> Result<()> {
-        bng.conn()?
-            .ack(
-                "CloseAdvancedIMU",
-                "ClosedAdvancedIMU",
-                &[
-                    ("name", rmpv::Value::from(self.name.as_str())),
-                    ("vid", rmpv::Value::from(self.vid.as_str())),
-                ],
-            )
-            .await?;
-        info!("Closed AdvancedIMU: \"{}\"", self.name);
-        Ok(())
+        bng.close_sensor(&self.name).await
     }
 
     pub fn name(&self) -> &str {
         &self.name
     }
 }
+
+impl GeSensor for AdvancedImu {
+    type Config = AdvancedImuConfig;
+    type Reading = Vec<ImuReading>;
+
+    async fn open_sensor(
+        name: impl Into<String> + Send,
+        bng: &mut BeamNg,
+        vehicle: Option<&Vehicle>,
+        config: AdvancedImuConfig,
+    ) -> Result<Self> {
+        AdvancedImu::open(name, bng, require_vehicle(vehicle, "Advanced IMU")?, config).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn poll_request(&self) -> (&'static str, Vec<(&str, rmpv::Value)>) {
+        (
+            "PollAdvancedImuGE",
+            vec![("name", rmpv::Value::from(self.name.as_str()))],
+        )
+    }
+
+    fn decode_poll(&self, resp: &StrDict) -> Vec<ImuReading> {
+        resp.get("data")
+            .map(|data| parse_readings(data, |m| parse_reading(&m)))
+            .unwrap_or_default()
+    }
+
+    async fn close(self, bng: &mut BeamNg) -> Result<()> {
+        AdvancedImu::close(self, bng).await
+    }
+}
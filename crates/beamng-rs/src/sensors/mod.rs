@@ -1,13 +1,197 @@
+use beamng_proto::types::{value_as_u64, value_to_str_dict, StrDict};
+use beamng_proto::{BngError, Result};
+
+use tracing::warn;
+
+use crate::beamng::BeamNg;
+use crate::vehicle::Vehicle;
+
 mod camera;
 mod electrics;
+mod ge_sensor;
 mod gps;
 mod imu;
+pub mod noise;
+mod powertrain;
+pub(crate) mod registry;
 mod sensor;
+mod sensor_rig;
 mod state;
+mod sync;
+mod timer;
+mod ultrasonic;
 
-pub use camera::{Camera, CameraConfig, CameraRawReadings};
+pub use camera::{
+    extract_bounding_boxes, write_calibration_yaml, AnnotationClasses, BoundingBox2D,
+    BoundingBoxFilter, BufferSource, Camera, CameraConfig, CameraFrame, CameraImages,
+    CameraIntrinsics, CameraRawReadings, CameraRawView, CameraRig, ColourImage, CoordinateFrame,
+    DepthImage, FrameStreamOptions, PixelLayout, PointFrame, RigCapture, RigTrigger, SemanticImage,
+    ShmemView, VehiclePose,
+};
 pub use electrics::{Electrics, ElectricsData};
-pub use gps::{Gps, GpsConfig, GpsReading};
+pub use ge_sensor::{GeSensor, GroupReadings, SensorGroup};
+pub use gps::{
+    nmea_checksum, EnuFrame, Geodetic, GnssErrorConfig, GnssErrorModel, Gps, GpsConfig, GpsReading,
+    NmeaFix, NmeaGenerator, NmeaSentences, Utm, WGS84_A, WGS84_F,
+};
 pub use imu::{AdvancedImu, AdvancedImuConfig, ImuReading};
+pub use powertrain::{Powertrain, PowertrainConfig, PowertrainDevice, PowertrainReading};
+pub use registry::{SensorInfo, SensorKind};
 pub use sensor::Sensor;
+pub use sensor_rig::{SensorEntry, SensorRig, SensorSpec};
 pub use state::State;
+pub use sync::{Interpolate, SampleBundle, SensorSync, DEFAULT_MAX_BUFFERED};
+pub use timer::Timer;
+pub use ultrasonic::{ring_poses, Ultrasonic, UltrasonicConfig, UltrasonicReading};
+
+/// Parse the readings of a GE or vehicle-engine poll response.
+///
+/// The simulator returns either an array of readings, a map keyed by step index
+/// (`0.0`, `1.0`, ...) with one reading per physics step since the last poll, or,
+/// for vehicle-engine polls, a single reading map with string keys.
+pub(crate) fn parse_readings<T>(val: &rmpv::Value, parse: impl Fn(StrDict) -> T) -> Vec<T> {
+    let step = |k: &rmpv::Value| k.as_f64().or_else(|| k.as_u64().map(|i| i as f64));
+    match val {
+        rmpv::Value::Array(arr) => arr
+            .iter()
+            .filter_map(|v| value_to_str_dict(v.clone()).map(&parse))
+            .collect(),
+        rmpv::Value::Map(pairs) if pairs.iter().all(|(k, _)| step(k).is_some()) => {
+            let mut readings: Vec<(f64, T)> = pairs
+                .iter()
+                .filter_map(|(k, v)| Some((step(k)?, parse(value_to_str_dict(v.clone())?))))
+                .collect();
+            readings.sort_by(|a, b| a.0.total_cmp(&b.0));
+            readings.into_iter().map(|(_, r)| r).collect()
+        }
+        rmpv::Value::Map(_) => value_to_str_dict(val.clone())
+            .map(|map| vec![parse(map)])
+            .unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+/// Fetch the simulator-side ID of a sensor opened in immediate mode, which
+/// vehicle-engine polls address it by.
+///
+/// The sensor must already be open and registered. If the ID cannot be fetched, the
+/// sensor is closed again, since the caller returns no handle to close it with.
+pub(crate) async fn fetch_sensor_id(bng: &mut BeamNg, request: &str, name: &str) -> Result<u64> {
+    let fetched = async {
+        let resp = bng
+            .conn()?
+            .request(request, &[("name", rmpv::Value::from(name))])
+            .await?;
+        resp.get("data")
+            .and_then(value_as_u64)
+            .ok_or_else(|| BngError::ValueError(format!("Missing sensor ID in {request} response")))
+    }
+    .await;
+    if fetched.is_err() {
+        if let Err(e) = bng.close_sensor(name).await {
+            warn!("Failed to close \"{name}\" after its ID could not be fetched: {e}");
+        }
+    }
+    fetched
+}
+
+/// A sensor that can be polled directly from the vehicle engine.
+pub(crate) struct VePoll<'a> {
+    /// The sensor kind for error messages, e.g. `"GPS"`.
+    pub label: &'a str,
+    pub name: &'a str,
+    pub vid: &'a str,
+    /// The simulator-side ID, set only for sensors opened with `is_send_immediately`.
+    pub sensor_id: Option<u64>,
+    pub request: &'a str,
+}
+
+impl VePoll<'_> {
+    /// Poll the sensor over `vehicle`'s own connection, bypassing the game engine.
+    pub(crate) async fn poll<T>(
+        &self,
+        vehicle: &mut Vehicle,
+        parse: impl Fn(StrDict) -> T,
+    ) -> Result<Vec<T>> {
+        let sensor_id = self.sensor_id.ok_or_else(|| {
+            BngError::ValueError(format!(
+                "This {} was not created with is_send_immediately=true.",
+                self.label
+            ))
+        })?;
+        if vehicle.vid != self.vid {
+            return Err(BngError::ValueError(format!(
+                "{} \"{}\" is attached to vehicle \"{}\", not \"{}\"",
+                self.label, self.name, self.vid, vehicle.vid
+            )));
+        }
+        let resp = vehicle
+            .send_vehicle_request(
+                self.request,
+                &[
+                    ("name", rmpv::Value::from(self.name)),
+                    ("sensorId", rmpv::Value::from(sensor_id)),
+                ],
+            )
+            .await?;
+        Ok(resp
+            .get("data")
+            .map(|data| parse_readings(data, parse))
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{mock_sim, req_type};
+
+    #[test]
+    fn test_parse_readings_shapes() {
+        let reading = |time: f64| rmpv::Value::Map(vec![("time".into(), time.into())]);
+        let time = |m: StrDict| m["time"].as_f64().unwrap();
+
+        // A single vehicle-engine reading.
+        assert_eq!(parse_readings(&reading(1.5), time), vec![1.5]);
+        // Readings keyed by step, out of order and with integer or float keys.
+        let keyed = rmpv::Value::Map(vec![
+            (2.0.into(), reading(0.3)),
+            (0.into(), reading(0.1)),
+            (1.0.into(), reading(0.2)),
+        ]);
+        assert_eq!(parse_readings(&keyed, time), vec![0.1, 0.2, 0.3]);
+        let array = rmpv::Value::Array(vec![reading(0.1), reading(0.2)]);
+        assert_eq!(parse_readings(&array, time), vec![0.1, 0.2]);
+        // No readings since the last poll.
+        assert!(parse_readings(&rmpv::Value::Map(vec![]), time).is_empty());
+        assert!(parse_readings(&rmpv::Value::Nil, time).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sensor_closed_when_id_fetch_fails() {
+        let (port, sim) = mock_sim(|req| match req_type(req) {
+            "OpenGPS" => vec![("type", rmpv::Value::from("OpenedGPS"))],
+            "CloseGPS" => vec![("type", rmpv::Value::from("ClosedGPS"))],
+            // No `data` field, so the ID is missing.
+            t => vec![("type", rmpv::Value::from(t))],
+        })
+        .await;
+        let mut bng = BeamNg::new("127.0.0.1", port).connect().await.unwrap();
+        let ego = Vehicle::new("ego", "etk800");
+        let config = GpsConfig {
+            is_send_immediately: true,
+            ..Default::default()
+        };
+        assert!(Gps::open("gps", &mut bng, &ego, config).await.is_err());
+        assert!(bng.list_sensors().is_empty());
+        bng.disconnect();
+
+        let types: Vec<_> = sim
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| req_type(r).to_string())
+            .collect();
+        assert_eq!(types, vec!["OpenGPS", "GetGPSId", "CloseGPS"]);
+    }
+}
@@ -0,0 +1,121 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use super::{gaussian, Fault};
+use crate::sensors::ColourImage;
+
+/// Additive Gaussian noise on the colour channels of RGBA pixels.
+#[derive(Debug, Clone)]
+pub struct PixelNoise {
+    /// Standard deviation in 8-bit intensity levels.
+    pub std: f64,
+    rng: ChaCha8Rng,
+}
+
+impl PixelNoise {
+    pub fn new(std: f64, seed: u64) -> Self {
+        Self {
+            std,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Perturb a raw RGBA buffer in place, leaving alpha untouched.
+    pub fn apply_rgba(&mut self, data: &mut [u8]) {
+        for px in data.chunks_exact_mut(4) {
+            for c in &mut px[..3] {
+                let v = *c as f64 + gaussian(&mut self.rng, self.std);
+                *c = v.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+}
+
+impl Fault<ColourImage> for PixelNoise {
+    fn apply(&mut self, _time: f64, mut value: ColourImage) -> Option<ColourImage> {
+        self.apply_rgba(&mut value.data);
+        Some(value)
+    }
+}
+
+/// A box blur with a square kernel of side `2 * radius + 1`, applied separably.
+///
+/// Pixels beyond the border are clamped to the edge.
+#[derive(Debug, Clone, Copy)]
+pub struct BoxBlur {
+    pub radius: u32,
+}
+
+impl BoxBlur {
+    pub fn new(radius: u32) -> Self {
+        Self { radius }
+    }
+
+    /// Blur a raw RGBA buffer of the given size in place, leaving alpha untouched.
+    pub fn apply_rgba(&self, data: &mut [u8], width: u32, height: u32) {
+        let (w, h, r) = (width as usize, height as usize, self.radius as isize);
+        if r == 0 || w == 0 || h == 0 || data.len() < w * h * 4 {
+            return;
+        }
+        // Horizontal pass into `tmp`, vertical pass back into `data`.
+        let mut tmp = data[..w * h * 4].to_vec();
+        blur_pass(data, &mut tmp, w, h, r, true);
+        blur_pass(&tmp, data, w, h, r, false);
+    }
+}
+
+/// One separable box-blur pass over RGB channels, horizontal or vertical.
+fn blur_pass(src: &[u8], dst: &mut [u8], w: usize, h: usize, r: isize, horizontal: bool) {
+    let n = (2 * r + 1) as u32;
+    for y in 0..h {
+        for x in 0..w {
+            let mut sum = [0u32; 3];
+            for k in -r..=r {
+                let (sx, sy) = if horizontal {
+                    ((x as isize + k).clamp(0, w as isize - 1) as usize, y)
+                } else {
+                    (x, (y as isize + k).clamp(0, h as isize - 1) as usize)
+                };
+                let i = (sy * w + sx) * 4;
+                for c in 0..3 {
+                    sum[c] += src[i + c] as u32;
+                }
+            }
+            let i = (y * w + x) * 4;
+            for c in 0..3 {
+                dst[i + c] = ((sum[c] + n / 2) / n) as u8;
+            }
+        }
+    }
+}
+
+impl Fault<ColourImage> for BoxBlur {
+    fn apply(&mut self, _time: f64, mut value: ColourImage) -> Option<ColourImage> {
+        self.apply_rgba(&mut value.data, value.width, value.height);
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blur_and_pixel_noise() {
+        // 3x1 image: a single white pixel in the middle.
+        let mut data = vec![0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255];
+        BoxBlur::new(1).apply_rgba(&mut data, 3, 1);
+        assert_eq!(
+            data,
+            vec![85, 85, 85, 255, 85, 85, 85, 255, 85, 85, 85, 255]
+        );
+
+        let mut a = vec![128; 64];
+        let mut b = a.clone();
+        PixelNoise::new(5.0, 3).apply_rgba(&mut a);
+        PixelNoise::new(5.0, 3).apply_rgba(&mut b);
+        assert_eq!(a, b);
+        assert!(a.iter().skip(3).step_by(4).all(|&alpha| alpha == 128));
+        assert!(a.chunks(4).any(|p| p[0] != 128));
+    }
+}
@@ -0,0 +1,457 @@
+//! Seedable noise and fault injection for sensor readings.
+//!
+//! Every model implements [`Fault`], which maps one timestamped sample to an output
+//! sample or drops it. Models draw from their own generator seeded at construction,
+//! so a [`Pipeline`] built from the same seeds and fed the same samples always
+//! produces the same output.
+
+use std::collections::VecDeque;
+
+use beamng_proto::types::Vec3;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Distribution, Normal};
+
+use super::{GpsReading, ImuReading, PowertrainReading};
+
+mod camera;
+
+pub use camera::{BoxBlur, PixelNoise};
+
+/// Draw from `N(0, std²)`, or return 0 for a non-positive `std`.
+fn gaussian(rng: &mut ChaCha8Rng, std: f64) -> f64 {
+    match Normal::new(0.0, std) {
+        Ok(n) if std > 0.0 => n.sample(rng),
+        _ => 0.0,
+    }
+}
+
+/// A sample with a simulation timestamp.
+pub trait Timestamped {
+    /// Simulation time in seconds.
+    fn time(&self) -> f64;
+}
+
+impl Timestamped for ImuReading {
+    fn time(&self) -> f64 {
+        self.time
+    }
+}
+
+impl Timestamped for GpsReading {
+    fn time(&self) -> f64 {
+        self.time
+    }
+}
+
+impl Timestamped for PowertrainReading {
+    fn time(&self) -> f64 {
+        self.time
+    }
+}
+
+/// A noise or fault model over samples of type `T`.
+pub trait Fault<T> {
+    /// Process the sample taken at `time`, returning `None` if it is dropped.
+    fn apply(&mut self, time: f64, value: T) -> Option<T>;
+
+    /// Process a batch of timestamped samples, such as the result of a sensor poll.
+    fn apply_batch(&mut self, values: Vec<T>) -> Vec<T>
+    where
+        T: Timestamped,
+        Self: Sized,
+    {
+        values
+            .into_iter()
+            .filter_map(|v| {
+                let time = v.time();
+                self.apply(time, v)
+            })
+            .collect()
+    }
+}
+
+/// Values made of `f64` channels that additive noise can perturb.
+pub trait Channels {
+    fn for_each_channel(&mut self, f: &mut dyn FnMut(&mut f64));
+}
+
+impl Channels for f64 {
+    fn for_each_channel(&mut self, f: &mut dyn FnMut(&mut f64)) {
+        f(self)
+    }
+}
+
+impl Channels for Vec3 {
+    fn for_each_channel(&mut self, f: &mut dyn FnMut(&mut f64)) {
+        f(&mut self.0);
+        f(&mut self.1);
+        f(&mut self.2);
+    }
+}
+
+impl Channels for Vec<f64> {
+    fn for_each_channel(&mut self, f: &mut dyn FnMut(&mut f64)) {
+        self.iter_mut().for_each(f)
+    }
+}
+
+/// Additive error on every channel: white Gaussian noise, a constant bias and a
+/// random-walk drift.
+///
+/// Each channel has its own random-walk state. The walk advances by
+/// `N(0, random_walk_std² · dt)` between samples.
+#[derive(Debug, Clone)]
+pub struct AdditiveNoise {
+    /// Standard deviation of the white noise.
+    pub std: f64,
+    /// Constant offset added to every channel.
+    pub bias: f64,
+    /// Random-walk rate, in units per √s.
+    pub random_walk_std: f64,
+    rng: ChaCha8Rng,
+    walk: Vec<f64>,
+    last_time: Option<f64>,
+}
+
+impl AdditiveNoise {
+    /// A model that adds nothing until configured with the `with_*` methods.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            std: 0.0,
+            bias: 0.0,
+            random_walk_std: 0.0,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            walk: Vec::new(),
+            last_time: None,
+        }
+    }
+
+    pub fn with_gaussian(mut self, std: f64) -> Self {
+        self.std = std;
+        self
+    }
+
+    pub fn with_bias(mut self, bias: f64) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    pub fn with_random_walk(mut self, std: f64) -> Self {
+        self.random_walk_std = std;
+        self
+    }
+
+    /// Perturb the channels of a value taken at `time`.
+    pub fn perturb<T: Channels + ?Sized>(&mut self, time: f64, value: &mut T) {
+        let dt = self.last_time.map_or(0.0, |last| (time - last).max(0.0));
+        self.last_time = Some(time);
+        let step = self.random_walk_std * dt.sqrt();
+
+        let mut i = 0;
+        value.for_each_channel(&mut |c| {
+            if self.walk.len() <= i {
+                self.walk.push(0.0);
+            }
+            self.walk[i] += gaussian(&mut self.rng, step);
+            *c += self.bias + self.walk[i] + gaussian(&mut self.rng, self.std);
+            i += 1;
+        });
+    }
+}
+
+impl<T: Channels> Fault<T> for AdditiveNoise {
+    fn apply(&mut self, time: f64, mut value: T) -> Option<T> {
+        self.perturb(time, &mut value);
+        Some(value)
+    }
+}
+
+/// Accelerometer and gyroscope noise for [`ImuReading`]s.
+///
+/// `accel` perturbs `acc_raw` and `acc_smooth`, `gyro` perturbs `ang_vel` and
+/// `ang_vel_smooth`; raw and smoothed channels get independent noise.
+#[derive(Debug, Clone)]
+pub struct ImuNoise {
+    pub accel: AdditiveNoise,
+    pub gyro: AdditiveNoise,
+}
+
+impl Fault<ImuReading> for ImuNoise {
+    fn apply(&mut self, time: f64, mut value: ImuReading) -> Option<ImuReading> {
+        let mut acc = vec![
+            value.acc_raw.0,
+            value.acc_raw.1,
+            value.acc_raw.2,
+            value.acc_smooth.0,
+            value.acc_smooth.1,
+            value.acc_smooth.2,
+        ];
+        let mut gyro = vec![
+            value.ang_vel.0,
+            value.ang_vel.1,
+            value.ang_vel.2,
+            value.ang_vel_smooth.0,
+            value.ang_vel_smooth.1,
+            value.ang_vel_smooth.2,
+        ];
+        self.accel.perturb(time, &mut acc);
+        self.gyro.perturb(time, &mut gyro);
+        value.acc_raw = (acc[0], acc[1], acc[2]);
+        value.acc_smooth = (acc[3], acc[4], acc[5]);
+        value.ang_vel = (gyro[0], gyro[1], gyro[2]);
+        value.ang_vel_smooth = (gyro[3], gyro[4], gyro[5]);
+        Some(value)
+    }
+}
+
+/// Perturbs `x`/`y` and moves `lon`/`lat` by the same east/north offset, as
+/// [`GnssErrorModel`](super::GnssErrorModel) does.
+impl Fault<GpsReading> for AdditiveNoise {
+    fn apply(&mut self, time: f64, value: GpsReading) -> Option<GpsReading> {
+        let mut xy = vec![value.x, value.y];
+        self.perturb(time, &mut xy);
+        Some(value.shifted(xy[0] - value.x, xy[1] - value.y))
+    }
+}
+
+/// Delays samples by a fixed latency.
+///
+/// A sample taken at `t` is released once a sample at or after `t + delay` arrives.
+/// [`apply`](Fault::apply) releases at most one sample per call, the oldest due, so
+/// samples that become due together come out on later calls; use
+/// [`apply_batch`](Fault::apply_batch) to receive every due sample at once.
+#[derive(Debug, Clone)]
+pub struct Latency<T> {
+    pub delay: f64,
+    queue: VecDeque<(f64, T)>,
+}
+
+impl<T> Latency<T> {
+    pub fn new(delay: f64) -> Self {
+        Self {
+            delay,
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl<T> Latency<T> {
+    /// Pop the oldest queued sample if it is due at `time`.
+    fn pop_due(&mut self, time: f64) -> Option<T> {
+        let (t, _) = self.queue.front()?;
+        // Tolerate rounding in sums of simulation time steps.
+        if *t + self.delay > time + 1e-9 {
+            return None;
+        }
+        self.queue.pop_front().map(|(_, v)| v)
+    }
+}
+
+impl<T> Fault<T> for Latency<T> {
+    fn apply(&mut self, time: f64, value: T) -> Option<T> {
+        self.queue.push_back((time, value));
+        self.pop_due(time)
+    }
+
+    fn apply_batch(&mut self, values: Vec<T>) -> Vec<T>
+    where
+        T: Timestamped,
+    {
+        let mut released = Vec::new();
+        for value in values {
+            let time = value.time();
+            self.queue.push_back((time, value));
+            while let Some(v) = self.pop_due(time) {
+                released.push(v);
+            }
+        }
+        released
+    }
+}
+
+/// Drops each sample independently with a fixed probability.
+#[derive(Debug, Clone)]
+pub struct Dropout {
+    pub probability: f64,
+    rng: ChaCha8Rng,
+}
+
+impl Dropout {
+    pub fn new(probability: f64, seed: u64) -> Self {
+        Self {
+            probability,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl<T> Fault<T> for Dropout {
+    fn apply(&mut self, _time: f64, value: T) -> Option<T> {
+        (self.rng.gen::<f64>() >= self.probability).then_some(value)
+    }
+}
+
+/// Freezes the output on one sample for a while, as a stuck sensor would.
+///
+/// Faults start at random with `rate` per second of simulation time and last
+/// `duration` seconds, during which the sample that triggered the fault is repeated
+/// verbatim.
+#[derive(Debug, Clone)]
+pub struct StuckFault<T> {
+    pub rate: f64,
+    pub duration: f64,
+    rng: ChaCha8Rng,
+    held: Option<(f64, T)>,
+    last_time: Option<f64>,
+}
+
+impl<T> StuckFault<T> {
+    pub fn new(rate: f64, duration: f64, seed: u64) -> Self {
+        Self {
+            rate,
+            duration,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            held: None,
+            last_time: None,
+        }
+    }
+
+    /// Whether the output is currently frozen.
+    pub fn is_stuck(&self) -> bool {
+        self.held.is_some()
+    }
+}
+
+impl<T: Clone> Fault<T> for StuckFault<T> {
+    fn apply(&mut self, time: f64, value: T) -> Option<T> {
+        let dt = self.last_time.map_or(0.0, |last| (time - last).max(0.0));
+        self.last_time = Some(time);
+
+        if let Some((until, held)) = &self.held {
+            if time < *until {
+                return Some(held.clone());
+            }
+            self.held = None;
+        }
+        if self.rate > 0.0 && dt > 0.0 && self.rng.gen::<f64>() < 1.0 - (-self.rate * dt).exp() {
+            self.held = Some((time + self.duration, value.clone()));
+        }
+        Some(value)
+    }
+}
+
+/// A sequence of models applied in order; a sample dropped by one stage skips the rest.
+///
+/// # Example
+/// ```
+/// use beamng_rs::sensors::noise::{AdditiveNoise, Dropout, Fault, Latency, Pipeline};
+///
+/// let mut noise = Pipeline::new()
+///     .then(AdditiveNoise::new(1).with_gaussian(0.1).with_random_walk(0.01))
+///     .then(Dropout::new(0.05, 2))
+///     .then(Latency::new(0.02));
+/// let out: Vec<Option<f64>> = (0..10).map(|i| noise.apply(i as f64 * 0.01, 1.0)).collect();
+/// ```
+pub struct Pipeline<T> {
+    stages: Vec<Box<dyn Fault<T> + Send>>,
+}
+
+impl<T> Default for Pipeline<T> {
+    fn default() -> Self {
+        Self { stages: Vec::new() }
+    }
+}
+
+impl<T> Pipeline<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a stage.
+    pub fn then(mut self, stage: impl Fault<T> + Send + 'static) -> Self {
+        self.stages.push(Box::new(stage));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+}
+
+impl<T> Fault<T> for Pipeline<T> {
+    fn apply(&mut self, time: f64, value: T) -> Option<T> {
+        self.stages
+            .iter_mut()
+            .try_fold(value, |v, stage| stage.apply(time, v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::{EnuFrame, Geodetic};
+
+    fn run(seed: u64) -> Vec<Option<Vec3>> {
+        let mut p = Pipeline::new()
+            .then(
+                AdditiveNoise::new(seed)
+                    .with_gaussian(0.1)
+                    .with_bias(1.0)
+                    .with_random_walk(0.5),
+            )
+            .then(Dropout::new(0.3, seed + 1))
+            .then(StuckFault::new(2.0, 0.05, seed + 2));
+        (0..100)
+            .map(|i| p.apply(i as f64 * 0.01, (0.0, 0.0, 0.0)))
+            .collect()
+    }
+
+    #[test]
+    fn test_pipeline_is_deterministic() {
+        let a = run(5);
+        assert_eq!(a, run(5));
+        assert_ne!(a, run(6));
+        let kept: Vec<_> = a.iter().flatten().collect();
+        assert!(kept.len() < 100 && kept.len() > 40);
+        // The bias dominates the mean.
+        let mean = kept.iter().map(|v| v.0).sum::<f64>() / kept.len() as f64;
+        assert!((mean - 1.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_latency() {
+        let mut latency = Latency::new(0.02);
+        let out: Vec<_> = (0..5).map(|i| latency.apply(i as f64 * 0.01, i)).collect();
+        assert_eq!(out, vec![None, None, Some(0), Some(1), Some(2)]);
+
+        // A gap in the input makes several samples due at once; none are lost.
+        let reading = |time| GpsReading {
+            time,
+            ..Default::default()
+        };
+        let mut latency = Latency::new(0.02);
+        let out = latency.apply_batch([0.0, 0.01, 0.015, 0.1].map(reading).to_vec());
+        let times: Vec<_> = out.iter().map(|r| r.time).collect();
+        assert_eq!(times, vec![0.0, 0.01, 0.015]);
+    }
+
+    #[test]
+    fn test_gps_noise_moves_lat_lon() {
+        let mut noise = AdditiveNoise::new(3).with_bias(5.0);
+        let reading = GpsReading {
+            lat: 45.0,
+            lon: 7.0,
+            ..Default::default()
+        };
+        let r = noise.apply(0.0, reading).unwrap();
+        let frame = EnuFrame::new(Geodetic::new(45.0, 7.0, 0.0));
+        let enu = frame.geodetic_to_enu(Geodetic::new(r.lat, r.lon, 0.0));
+        assert!((enu.0 - r.x).abs() < 1e-6 && (enu.1 - r.y).abs() < 1e-6);
+        assert!(r.x.abs() > 1.0);
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use beamng_proto::types::{value_to_str_dict, value_to_string, StrDict};
+use beamng_proto::Result;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::beamng::BeamNg;
+use crate::sensors::ge_sensor::require_vehicle;
+use crate::sensors::registry::{mark_dropped, SharedSensorRegistry};
+use crate::sensors::{fetch_sensor_id, parse_readings, GeSensor, SensorKind, VePoll};
+use crate::vehicle::Vehicle;
+
+/// Configuration for a [`Powertrain`] sensor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PowertrainConfig {
+    pub gfx_update_time: f64,
+    pub physics_update_time: f64,
+    pub is_send_immediately: bool,
+}
+
+impl Default for PowertrainConfig {
+    fn default() -> Self {
+        Self {
+            gfx_update_time: 0.0,
+            physics_update_time: 0.01,
+            is_send_immediately: false,
+        }
+    }
+}
+
+/// The state of a single powertrain device (engine, gearbox, differential, wheel, ...).
+#[derive(Debug, Clone, Default)]
+pub struct PowertrainDevice {
+    /// The device name, e.g. `"mainEngine"` or `"wheelaxleFL"`.
+    pub name: String,
+    /// The device type, e.g. `"combustionEngine"`, `"manualGearbox"` or `"differential"`.
+    pub device_type: Option<String>,
+    /// The operating mode reported by the device, if any.
+    pub mode: Option<String>,
+    /// The name of the device this one is driven by.
+    pub parent_name: Option<String>,
+    /// The output index on the parent device this one is connected to.
+    pub parent_output_index: Option<i64>,
+    /// Input angular velocity in rad/s.
+    pub input_av: f64,
+    /// Current gear ratio (1.0 for devices without gearing).
+    pub gear_ratio: f64,
+    pub is_broken: bool,
+    /// Output torque in N·m, one entry per output (`outputTorque1`, `outputTorque2`, ...).
+    pub output_torque: Vec<f64>,
+    /// Output angular velocity in rad/s, one entry per output (`outputAV1`, `outputAV2`, ...).
+    pub output_av: Vec<f64>,
+}
+
+/// A single powertrain reading: the state of every device at one simulation time.
+#[derive(Debug, Clone, Default)]
+pub struct PowertrainReading {
+    pub time: f64,
+    /// Devices keyed by name.
+    pub devices: HashMap<String, PowertrainDevice>,
+}
+
+impl PowertrainReading {
+    /// Get a device by name.
+    pub fn device(&self, name: &str) -> Option<&PowertrainDevice> {
+        self.devices.get(name)
+    }
+
+    /// Iterate over all devices of the given type (e.g. `"differential"`).
+    pub fn devices_of_type<'a>(
+        &'a self,
+        device_type: &'a str,
+    ) -> impl Iterator<Item = &'a PowertrainDevice> + 'a {
+        self.devices
+            .values()
+            .filter(move |d| d.device_type.as_deref() == Some(device_type))
+    }
+}
+
+/// Collect numbered per-output values (`{prefix}1`, `{prefix}2`, ...) until one is missing.
+fn numbered_values(map: &StrDict, prefix: &str) -> Vec<f64> {
+    (1..)
+        .map_while(|i| map.get(&format!("{prefix}{i}")).and_then(|v| v.as_f64()))
+        .collect()
+}
+
+fn parse_device(name: &str, map: &StrDict) -> PowertrainDevice {
+    PowertrainDevice {
+        name: map
+            .get("name")
+            .and_then(value_to_string)
+            .unwrap_or_else(|| name.to_string()),
+        device_type: map.get("type").and_then(value_to_string),
+        mode: map.get("mode").and_then(value_to_string),
+        parent_name: map.get("parentName").and_then(value_to_string),
+        parent_output_index: map.get("parentOutputIndex").and_then(|v| v.as_i64()),
+        input_av: map.get("inputAV").and_then(|v| v.as_f64()).unwrap_or(0.0),
+        gear_ratio: map.get("gearRatio").and_then(|v| v.as_f64()).unwrap_or(1.0),
+        is_broken: map
+            .get("isBroken")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        output_torque: numbered_values(map, "outputTorque"),
+        output_av: numbered_values(map, "outputAV"),
+    }
+}
+
+fn parse_reading(map: StrDict) -> PowertrainReading {
+    let mut time = 0.0;
+    let mut devices = HashMap::new();
+    for (key, val) in map {
+        if key == "time" {
+            time = val.as_f64().unwrap_or(0.0);
+            continue;
+        }
+        if let Some(dev) = value_to_str_dict(val) {
+            let device = parse_device(&key, &dev);
+            devices.insert(key, device);
+        }
+    }
+    PowertrainReading { time, devices }
+}
+
+/// A powertrain sensor attached to a vehicle (GE-level).
+///
+/// Reports per-device angular velocity and torque for the whole drivetrain.
+pub struct Powertrain {
+    name: String,
+    vid: String,
+    /// The simulator-side ID, known only for sensors opened with `is_send_immediately`.
+    sensor_id: Option<u64>,
+    registry: SharedSensorRegistry,
+}
+
+impl Drop for Powertrain {
+    fn drop(&mut self) {
+        mark_dropped(&self.registry, &self.name);
+    }
+}
+
+impl Powertrain {
+    /// Open a powertrain sensor in the simulator, attached to the given vehicle.
+    pub async fn open(
+        name: impl Into<String>,
+        bng: &mut BeamNg,
+        vehicle: &Vehicle,
+        config: PowertrainConfig,
+    ) -> Result<Self> {
+        let name = name.into();
+        bng.check_sensor_name(&name)?;
+        let vid = vehicle.vid.clone();
+
+        let fields: Vec<(&str, rmpv::Value)> = vec![
+            ("name", rmpv::Value::from(name.as_str())),
+            ("vid", rmpv::Value::from(vid.as_str())),
+            ("GFXUpdateTime", rmpv::Value::from(config.gfx_update_time)),
+            (
+                "physicsUpdateTime",
+                rmpv::Value::from(config.physics_update_time),
+            ),
+            (
+                "isSendImmediately",
+                rmpv::Value::from(config.is_send_immediately),
+            ),
+        ];
+
+        bng.conn()?
+            .ack("OpenPowertrain", "OpenedPowertrain", &fields)
+            .await?;
+
+        info!("Opened Powertrain: \"{}\"", name);
+        bng.register_sensor(SensorKind::Powertrain, &name, Some(&vid));
+
+        // The vehicle-engine poll addresses the sensor by its simulator-side ID.
+        let sensor_id = if config.is_send_immediately {
+            Some(fetch_sensor_id(bng, "GetPowertrainId", &name).await?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            name,
+            vid,
+            sensor_id,
+            registry: bng.shared_sensor_registry(),
+        })
+    }
+
+    /// Poll the sensor for readings.
+    ///
+    /// Returns a list of readings accumulated since the last poll, one per physics
+    /// update, so high-rate torque traces can be reconstructed.
+    pub async fn poll(&self, bng: &mut BeamNg) -> Result<Vec<PowertrainReading>> {
+        GeSensor::poll(self, bng).await
+    }
+
+    /// Poll the latest reading directly from the vehicle engine.
+    ///
+    /// The request goes over the vehicle's own connection, bypassing the game engine.
+    /// Requires the sensor to have been opened with `is_send_immediately: true`
+    /// and `vehicle` to be the connected vehicle it is attached to.
+    pub async fn poll_ve(&self, vehicle: &mut Vehicle) -> Result<Vec<PowertrainReading>> {
+        VePoll {
+            label: "powertrain sensor",
+            name: &self.name,
+            vid: &self.vid,
+            sensor_id: self.sensor_id,
+            request: "PollPowertrainVE",
+        }
+        .poll(vehicle, parse_reading)
+        .await
+    }
+
+    /// Close the sensor.
+    pub async fn close(self, bng: &mut BeamNg) -> Result<()> {
+        bng.close_sensor(&self.name).await
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl GeSensor for Powertrain {
+    type Config = PowertrainConfig;
+    type Reading = Vec<PowertrainReading>;
+
+    async fn open_sensor(
+        name: impl Into<String> + Send,
+        bng: &mut BeamNg,
+        vehicle: Option<&Vehicle>,
+        config: PowertrainConfig,
+    ) -> Result<Self> {
+        Powertrain::open(
+            name,
+            bng,
+            require_vehicle(vehicle, "powertrain sensor")?,
+            config,
+        )
+        .await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn poll_request(&self) -> (&'static str, Vec<(&str, rmpv::Value)>) {
+        (
+            "PollPowertrainGE",
+            vec![("name", rmpv::Value::from(self.name.as_str()))],
+        )
+    }
+
+    fn decode_poll(&self, resp: &StrDict) -> Vec<PowertrainReading> {
+        resp.get("data")
+            .map(|data| parse_readings(data, parse_reading))
+            .unwrap_or_default()
+    }
+
+    async fn close(self, bng: &mut BeamNg) -> Result<()> {
+        Powertrain::close(self, bng).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_readings() {
+        let device = |ty: &str, extra: Vec<(&str, rmpv::Value)>| {
+            let mut pairs = vec![("type", rmpv::Value::from(ty)), ("inputAV", 100.0.into())];
+            pairs.extend(extra);
+            rmpv::Value::Map(pairs.into_iter().map(|(k, v)| (k.into(), v)).collect())
+        };
+        let reading = |time: f64| {
+            rmpv::Value::Map(vec![
+                ("time".into(), time.into()),
+                (
+                    "mainEngine".into(),
+                    device("combustionEngine", vec![("outputTorque1", 250.0.into())]),
+                ),
+                (
+                    "differential_F".into(),
+                    device(
+                        "differential",
+                        vec![
+                            ("parentName", "gearbox".into()),
+                            ("parentOutputIndex", 1.into()),
+                            ("gearRatio", 3.5.into()),
+                            ("outputAV1", 28.0.into()),
+                            ("outputAV2", 29.0.into()),
+                        ],
+                    ),
+                ),
+            ])
+        };
+        let resp = rmpv::Value::Map(vec![(1.into(), reading(0.02)), (0.into(), reading(0.01))]);
+        let readings = parse_readings(&resp, parse_reading);
+        assert_eq!(readings.len(), 2);
+        assert_eq!(readings[0].time, 0.01);
+
+        let diff = readings[1].device("differential_F").unwrap();
+        assert_eq!(diff.name, "differential_F");
+        assert_eq!(diff.parent_name.as_deref(), Some("gearbox"));
+        assert_eq!(diff.parent_output_index, Some(1));
+        assert_eq!(diff.gear_ratio, 3.5);
+        assert_eq!(diff.output_av, vec![28.0, 29.0]);
+        assert!(diff.output_torque.is_empty());
+        let engine = readings[1].device("mainEngine").unwrap();
+        assert_eq!(engine.output_torque, vec![250.0]);
+        assert_eq!(engine.gear_ratio, 1.0);
+        assert_eq!(readings[1].devices_of_type("differential").count(), 1);
+    }
+}
@@ -0,0 +1,189 @@
+use std::sync::{Arc, Mutex};
+
+use beamng_proto::{BngError, Connection, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// The kind of a simulator-side sensor tracked by [`BeamNg`](crate::BeamNg).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorKind {
+    Camera,
+    Gps,
+    AdvancedImu,
+    Powertrain,
+    Ultrasonic,
+}
+
+impl SensorKind {
+    /// The request and acknowledgement types that close a sensor of this kind.
+    fn close_types(self) -> (&'static str, &'static str) {
+        match self {
+            SensorKind::Camera => ("CloseCamera", "ClosedCamera"),
+            SensorKind::Gps => ("CloseGPS", "ClosedGPS"),
+            SensorKind::AdvancedImu => ("CloseAdvancedIMU", "ClosedAdvancedIMU"),
+            SensorKind::Powertrain => ("ClosePowertrain", "ClosedPowertrain"),
+            SensorKind::Ultrasonic => ("CloseUltrasonic", "ClosedUltrasonic"),
+        }
+    }
+}
+
+/// An open sensor, as listed by [`BeamNg::list_sensors`](crate::BeamNg::list_sensors).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SensorInfo {
+    pub name: String,
+    pub kind: SensorKind,
+    /// The vehicle the sensor is attached to, if any.
+    pub vid: Option<String>,
+}
+
+impl SensorInfo {
+    /// Send the close request for this sensor.
+    pub(crate) async fn close(&self, conn: &mut Connection) -> Result<()> {
+        let (req_type, ack_type) = self.kind.close_types();
+        let mut fields = vec![("name", rmpv::Value::from(self.name.as_str()))];
+        if self.kind != SensorKind::Camera {
+            let vid = match &self.vid {
+                Some(vid) => rmpv::Value::from(vid.as_str()),
+                None => rmpv::Value::from(0),
+            };
+            fields.push(("vid", vid));
+        }
+        conn.ack(req_type, ack_type, &fields).await
+    }
+}
+
+/// The sensors a [`BeamNg`](crate::BeamNg) has opened, plus those whose handles were
+/// dropped without being closed.
+#[derive(Debug, Default)]
+pub(crate) struct SensorRegistry {
+    open: Vec<SensorInfo>,
+    pending_close: Vec<String>,
+}
+
+/// The registry shared between a [`BeamNg`](crate::BeamNg) and sensor handles that
+/// need to report being dropped.
+pub(crate) type SharedSensorRegistry = Arc<Mutex<SensorRegistry>>;
+
+impl SensorRegistry {
+    pub(crate) fn list(&self) -> Vec<SensorInfo> {
+        self.open.clone()
+    }
+
+    /// Fail if a sensor with this name is already open.
+    pub(crate) fn check_name(&self, name: &str) -> Result<()> {
+        if let Some(existing) = self.open.iter().find(|s| s.name == name) {
+            return Err(BngError::ValueError(format!(
+                "A {:?} sensor named \"{name}\" is already open",
+                existing.kind
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<SensorInfo> {
+        self.open.iter().find(|s| s.name == name).cloned()
+    }
+
+    pub(crate) fn insert(&mut self, info: SensorInfo) {
+        self.open.push(info);
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) -> Option<SensorInfo> {
+        self.pending_close.retain(|n| n != name);
+        let i = self.open.iter().position(|s| s.name == name)?;
+        Some(self.open.remove(i))
+    }
+
+    /// Queue an open sensor to be closed on the next flush. Returns `false` if it is
+    /// not open or already queued.
+    pub(crate) fn mark_for_close(&mut self, name: &str) -> bool {
+        if !self.open.iter().any(|s| s.name == name) || self.pending_close.iter().any(|n| n == name)
+        {
+            return false;
+        }
+        self.pending_close.push(name.to_string());
+        true
+    }
+
+    /// Remove and return the sensors queued for closing.
+    pub(crate) fn take_pending(&mut self) -> Vec<SensorInfo> {
+        let names = std::mem::take(&mut self.pending_close);
+        names.iter().filter_map(|n| self.remove(n)).collect()
+    }
+
+    /// Remove and return the sensors attached to a vehicle.
+    pub(crate) fn take_vehicle(&mut self, vid: &str) -> Vec<SensorInfo> {
+        let (taken, kept) = std::mem::take(&mut self.open)
+            .into_iter()
+            .partition(|s| s.vid.as_deref() == Some(vid));
+        self.open = kept;
+        self.pending_close
+            .retain(|n| self.open.iter().any(|s| &s.name == n));
+        taken
+    }
+
+    /// Remove and return every sensor.
+    pub(crate) fn take_all(&mut self) -> Vec<SensorInfo> {
+        self.pending_close.clear();
+        std::mem::take(&mut self.open)
+    }
+}
+
+/// Queue the sensor behind a handle that was dropped while still open.
+///
+/// Called from the `Drop` impls of sensor handles; after a successful `close` the
+/// sensor is no longer registered and this does nothing.
+pub(crate) fn mark_dropped(registry: &SharedSensorRegistry, name: &str) {
+    let mut registry = registry.lock().unwrap_or_else(|e| e.into_inner());
+    if registry.mark_for_close(name) {
+        warn!("Sensor \"{name}\" dropped without close; it will be closed on the next step");
+    }
+}
+
+/// Close the given sensors, logging rather than returning failures since the
+/// simulator may already have removed them.
+pub(crate) async fn close_sensors(conn: &mut Connection, sensors: Vec<SensorInfo>) {
+    for sensor in sensors {
+        match sensor.close(conn).await {
+            Ok(()) => info!("Closed {:?}: \"{}\"", sensor.kind, sensor.name),
+            Err(e) => warn!("Failed to close {:?} \"{}\": {e}", sensor.kind, sensor.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(name: &str, vid: Option<&str>) -> SensorInfo {
+        SensorInfo {
+            name: name.into(),
+            kind: SensorKind::Gps,
+            vid: vid.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn test_registry_bookkeeping() {
+        let mut reg = SensorRegistry::default();
+        reg.insert(info("gps1", Some("ego")));
+        reg.insert(info("gps2", Some("other")));
+        reg.insert(info("gps3", Some("ego")));
+        assert!(reg.check_name("gps1").is_err());
+        assert!(reg.check_name("gps4").is_ok());
+
+        assert!(reg.mark_for_close("gps2"));
+        assert!(!reg.mark_for_close("gps2"));
+        assert!(!reg.mark_for_close("missing"));
+        let ego: Vec<_> = reg
+            .take_vehicle("ego")
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(ego, vec!["gps1", "gps3"]);
+        let pending: Vec<_> = reg.take_pending().into_iter().map(|s| s.name).collect();
+        assert_eq!(pending, vec!["gps2"]);
+        assert!(reg.list().is_empty());
+    }
+}
@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use beamng_proto::{BngError, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tracing::{info, warn};
+
+use super::{
+    AdvancedImu, AdvancedImuConfig, Camera, CameraConfig, GeSensor, Gps, GpsConfig, Powertrain,
+    PowertrainConfig, SensorGroup, SensorKind, Ultrasonic, UltrasonicConfig,
+};
+use crate::beamng::BeamNg;
+use crate::vehicle::Vehicle;
+
+/// The type and configuration of one sensor in a [`SensorRig`].
+#[derive(Debug, Clone)]
+pub enum SensorSpec {
+    Camera(CameraConfig),
+    Gps(GpsConfig),
+    AdvancedImu(AdvancedImuConfig),
+    Powertrain(PowertrainConfig),
+    Ultrasonic(UltrasonicConfig),
+}
+
+impl SensorSpec {
+    pub fn kind(&self) -> SensorKind {
+        match self {
+            SensorSpec::Camera(_) => SensorKind::Camera,
+            SensorSpec::Gps(_) => SensorKind::Gps,
+            SensorSpec::AdvancedImu(_) => SensorKind::AdvancedImu,
+            SensorSpec::Powertrain(_) => SensorKind::Powertrain,
+            SensorSpec::Ultrasonic(_) => SensorKind::Ultrasonic,
+        }
+    }
+
+    /// Whether the sensor can only be opened attached to a vehicle.
+    fn needs_vehicle(&self) -> bool {
+        !matches!(self, SensorSpec::Camera(_) | SensorSpec::Ultrasonic(_))
+    }
+}
+
+/// A named sensor in a [`SensorRig`].
+#[derive(Debug, Clone)]
+pub struct SensorEntry {
+    pub name: String,
+    /// The ID of the vehicle the sensor is attached to, if any.
+    pub vehicle: Option<String>,
+    pub spec: SensorSpec,
+}
+
+/// One entry of a rig file, before its configuration is checked against its type.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawEntry {
+    name: String,
+    #[serde(rename = "type")]
+    kind: SensorKind,
+    #[serde(default)]
+    vehicle: Option<String>,
+    #[serde(default)]
+    config: JsonValue,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRig {
+    #[serde(default)]
+    sensors: Vec<JsonValue>,
+}
+
+/// A set of named sensors and the vehicles they attach to, usually loaded from a
+/// TOML or JSON file so that sensor setups can change without recompiling.
+///
+/// Each entry has a `name`, a `type` (`camera`, `gps`, `advanced_imu`, `powertrain`
+/// or `ultrasonic`), an optional `vehicle` ID and an optional `config` table whose
+/// fields are those of the matching config struct. Missing fields take their
+/// defaults; unknown fields are errors.
+///
+/// ```toml
+/// [[sensors]]
+/// name = "front_cam"
+/// type = "camera"
+/// vehicle = "ego"
+/// config = { resolution = [1280, 720], pos = [0.0, -2.0, 1.5] }
+///
+/// [[sensors]]
+/// name = "gps"
+/// type = "gps"
+/// vehicle = "ego"
+/// config = { ref_lat = 48.8584, ref_lon = 2.2945 }
+/// ```
+///
+/// Errors from loading and [`validate`](Self::validate) name the offending entry by
+/// index and name.
+///
+/// # Example
+/// ```no_run
+/// # async fn example(bng: &mut beamng_rs::BeamNg, ego: &beamng_rs::vehicle::Vehicle) -> beamng_proto::Result<()> {
+/// use beamng_rs::sensors::{Camera, SensorRig};
+///
+/// let rig = SensorRig::from_file("rigs/front.toml")?;
+/// let mut sensors = rig.open(bng, [ego]).await?;
+/// let camera: Camera = sensors.remove("front_cam").unwrap();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SensorRig {
+    pub sensors: Vec<SensorEntry>,
+}
+
+impl SensorRig {
+    /// Parse and validate a rig from TOML.
+    pub fn from_toml_str(s: &str) -> Result<Self> {
+        let raw: RawRig = toml::from_str(s)
+            .map_err(|e| BngError::ValueError(format!("Invalid sensor rig TOML: {e}")))?;
+        Self::from_raw(raw)
+    }
+
+    /// Parse and validate a rig from JSON.
+    pub fn from_json_str(s: &str) -> Result<Self> {
+        let raw: RawRig = serde_json::from_str(s)
+            .map_err(|e| BngError::ValueError(format!("Invalid sensor rig JSON: {e}")))?;
+        Self::from_raw(raw)
+    }
+
+    /// Load and validate a rig file, choosing the format from its `.toml` or `.json`
+    /// extension.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml_str(&text),
+            Some("json") => Self::from_json_str(&text),
+            _ => Err(BngError::ValueError(format!(
+                "Sensor rig file {} must have a .toml or .json extension",
+                path.display()
+            ))),
+        }
+    }
+
+    fn from_raw(raw: RawRig) -> Result<Self> {
+        let sensors = raw
+            .sensors
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| parse_entry(i, value))
+            .collect::<Result<_>>()?;
+        let rig = Self { sensors };
+        rig.validate()?;
+        Ok(rig)
+    }
+
+    /// Check that names are unique and non-empty, and that sensors which must be
+    /// attached to a vehicle name one.
+    pub fn validate(&self) -> Result<()> {
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for (i, entry) in self.sensors.iter().enumerate() {
+            if entry.name.is_empty() {
+                return Err(entry_error(i, None, "name must not be empty"));
+            }
+            if let Some(first) = seen.insert(&entry.name, i) {
+                return Err(entry_error(
+                    i,
+                    Some(&entry.name),
+                    &format!("name is already used by sensors[{first}]"),
+                ));
+            }
+            if entry.vehicle.is_none() && entry.spec.needs_vehicle() {
+                return Err(entry_error(
+                    i,
+                    Some(&entry.name),
+                    &format!("a {:?} sensor needs a vehicle", entry.spec.kind()),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Open every sensor in the rig, attaching each to the vehicle in `vehicles`
+    /// whose ID it names.
+    ///
+    /// If a sensor fails to open, those already opened are closed again and the
+    /// error is returned.
+    pub async fn open<'v>(
+        &self,
+        bng: &mut BeamNg,
+        vehicles: impl IntoIterator<Item = &'v Vehicle>,
+    ) -> Result<SensorGroup> {
+        let vehicles: HashMap<&str, &Vehicle> =
+            vehicles.into_iter().map(|v| (v.vid.as_str(), v)).collect();
+        for (i, entry) in self.sensors.iter().enumerate() {
+            if let Some(vid) = &entry.vehicle {
+                if !vehicles.contains_key(vid.as_str()) {
+                    return Err(entry_error(
+                        i,
+                        Some(&entry.name),
+                        &format!("vehicle \"{vid}\" was not given"),
+                    ));
+                }
+            }
+        }
+
+        let mut group = SensorGroup::new();
+        for (i, entry) in self.sensors.iter().enumerate() {
+            let vehicle = entry.vehicle.as_deref().map(|vid| vehicles[vid]);
+            let name = entry.name.as_str();
+            let opened = match &entry.spec {
+                SensorSpec::Camera(c) => {
+                    open_into::<Camera>(&mut group, name, bng, vehicle, c).await
+                }
+                SensorSpec::Gps(c) => open_into::<Gps>(&mut group, name, bng, vehicle, c).await,
+                SensorSpec::AdvancedImu(c) => {
+                    open_into::<AdvancedImu>(&mut group, name, bng, vehicle, c).await
+                }
+                SensorSpec::Powertrain(c) => {
+                    open_into::<Powertrain>(&mut group, name, bng, vehicle, c).await
+                }
+                SensorSpec::Ultrasonic(c) => {
+                    open_into::<Ultrasonic>(&mut group, name, bng, vehicle, c).await
+                }
+            };
+            if let Err(e) = opened {
+                warn!("Failed to open sensors[{i}] (\"{name}\"): {e}");
+                if let Err(close_err) = group.close(bng).await {
+                    warn!("Failed to close sensor rig after error: {close_err}");
+                }
+                return Err(e);
+            }
+        }
+        info!("Opened sensor rig with {} sensors", group.len());
+        Ok(group)
+    }
+}
+
+async fn open_into<S: GeSensor + 'static>(
+    group: &mut SensorGroup,
+    name: &str,
+    bng: &mut BeamNg,
+    vehicle: Option<&Vehicle>,
+    config: &S::Config,
+) -> Result<()>
+where
+    S::Config: Clone,
+    S::Reading: 'static,
+{
+    group.push(S::open_sensor(name, bng, vehicle, config.clone()).await?);
+    Ok(())
+}
+
+fn entry_error(index: usize, name: Option<&str>, msg: &str) -> BngError {
+    match name {
+        Some(name) => BngError::ValueError(format!("sensors[{index}] (\"{name}\"): {msg}")),
+        None => BngError::ValueError(format!("sensors[{index}]: {msg}")),
+    }
+}
+
+fn parse_entry(index: usize, value: JsonValue) -> Result<SensorEntry> {
+    let name = value
+        .get("name")
+        .and_then(JsonValue::as_str)
+        .map(str::to_string);
+    let raw: RawEntry = serde_json::from_value(value)
+        .map_err(|e| entry_error(index, name.as_deref(), &e.to_string()))?;
+    let config_error =
+        |e: serde_json::Error| entry_error(index, Some(&raw.name), &format!("config: {e}"));
+    let spec = match raw.kind {
+        SensorKind::Camera => SensorSpec::Camera(parse_config(&raw.config).map_err(config_error)?),
+        SensorKind::Gps => SensorSpec::Gps(parse_config(&raw.config).map_err(config_error)?),
+        SensorKind::AdvancedImu => {
+            SensorSpec::AdvancedImu(parse_config(&raw.config).map_err(config_error)?)
+        }
+        SensorKind::Powertrain => {
+            SensorSpec::Powertrain(parse_config(&raw.config).map_err(config_error)?)
+        }
+        SensorKind::Ultrasonic => {
+            SensorSpec::Ultrasonic(parse_config(&raw.config).map_err(config_error)?)
+        }
+    };
+    Ok(SensorEntry {
+        name: raw.name,
+        vehicle: raw.vehicle,
+        spec,
+    })
+}
+
+/// Deserialize a sensor config, treating a missing one as all defaults.
+fn parse_config<C: DeserializeOwned + Default>(value: &JsonValue) -> serde_json::Result<C> {
+    if value.is_null() {
+        Ok(C::default())
+    } else {
+        C::deserialize(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rig_and_report_entry() {
+        let rig = SensorRig::from_toml_str(
+            r#"
+            [[sensors]]
+            name = "front_cam"
+            type = "camera"
+            vehicle = "ego"
+            config = { resolution = [1280, 720] }
+
+            [[sensors]]
+            name = "imu"
+            type = "advanced_imu"
+            vehicle = "ego"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(rig.sensors.len(), 2);
+        match &rig.sensors[0].spec {
+            SensorSpec::Camera(c) => {
+                assert_eq!(c.resolution, (1280, 720));
+                assert_eq!(c.field_of_view_y, CameraConfig::default().field_of_view_y);
+            }
+            other => panic!("unexpected spec {other:?}"),
+        }
+        assert_eq!(rig.sensors[1].spec.kind(), SensorKind::AdvancedImu);
+
+        let err = SensorRig::from_json_str(
+            r#"{"sensors": [
+                {"name": "gps", "type": "gps", "vehicle": "ego"},
+                {"name": "cam", "type": "camera", "config": {"resolutoin": [1, 1]}}
+            ]}"#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains("sensors[1] (\"cam\")") && err.contains("resolutoin"),
+            "{err}"
+        );
+
+        let err = SensorRig::from_json_str(r#"{"sensors": [{"name": "gps", "type": "gps"}]}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("sensors[0] (\"gps\")"), "{err}");
+    }
+}
@@ -0,0 +1,449 @@
+//! Alignment of readings from several sensors on simulation time.
+//!
+//! A [`SensorSync`] buffers timestamped readings per named channel and emits
+//! [`SampleBundle`]s holding one value per channel for a common time. Each channel
+//! is sampled with its own rule: the nearest reading, the latest reading at or before
+//! the bundle time, or a linear interpolation between the two readings around it.
+
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+
+use beamng_proto::types::Vec3;
+use beamng_proto::{BngError, Result};
+
+use super::noise::Timestamped;
+use super::{GpsReading, ImuReading};
+
+/// Values that can be linearly interpolated between two samples.
+pub trait Interpolate {
+    /// The value a fraction `t` in `[0, 1]` of the way from `self` to `other`.
+    fn interpolate(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Vec3 {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        (
+            self.0.interpolate(&other.0, t),
+            self.1.interpolate(&other.1, t),
+            self.2.interpolate(&other.2, t),
+        )
+    }
+}
+
+impl Interpolate for GpsReading {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        GpsReading {
+            time: self.time.interpolate(&other.time, t),
+            x: self.x.interpolate(&other.x, t),
+            y: self.y.interpolate(&other.y, t),
+            lon: self.lon.interpolate(&other.lon, t),
+            lat: self.lat.interpolate(&other.lat, t),
+        }
+    }
+}
+
+impl Interpolate for ImuReading {
+    /// Interpolates every field component-wise. The `dir_*` axes are not
+    /// re-orthonormalised, which is negligible between closely spaced readings.
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        ImuReading {
+            time: self.time.interpolate(&other.time, t),
+            mass: self.mass.interpolate(&other.mass, t),
+            acc_raw: self.acc_raw.interpolate(&other.acc_raw, t),
+            acc_smooth: self.acc_smooth.interpolate(&other.acc_smooth, t),
+            ang_vel: self.ang_vel.interpolate(&other.ang_vel, t),
+            ang_vel_smooth: self.ang_vel_smooth.interpolate(&other.ang_vel_smooth, t),
+            pos: self.pos.interpolate(&other.pos, t),
+            dir_x: self.dir_x.interpolate(&other.dir_x, t),
+            dir_y: self.dir_y.interpolate(&other.dir_y, t),
+            dir_z: self.dir_z.interpolate(&other.dir_z, t),
+        }
+    }
+}
+
+/// How a channel is sampled at a bundle time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alignment {
+    Nearest,
+    LatestBefore,
+    Interpolated,
+}
+
+/// Type-erased view of a [`Channel`].
+trait AnyChannel {
+    fn earliest_time(&self) -> Option<f64>;
+    fn latest_time(&self) -> Option<f64>;
+    fn sample(&self, time: f64, tolerance: Option<f64>) -> Option<Box<dyn Any>>;
+    fn prune(&mut self, time: f64);
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// The buffered readings of one channel, sorted by time.
+struct Channel<T> {
+    samples: VecDeque<(f64, T)>,
+    alignment: Alignment,
+    interpolate: Option<fn(&T, &T, f64) -> T>,
+}
+
+impl<T> Channel<T> {
+    fn new(alignment: Alignment, interpolate: Option<fn(&T, &T, f64) -> T>) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            alignment,
+            interpolate,
+        }
+    }
+
+    /// Insert a sample, then evict the oldest until at most `capacity` remain.
+    fn push(&mut self, time: f64, value: T, capacity: usize) {
+        let i = self.samples.partition_point(|(t, _)| *t <= time);
+        self.samples.insert(i, (time, value));
+        while self.samples.len() > capacity.max(1) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Index of the first sample after `time`.
+    fn after(&self, time: f64) -> usize {
+        self.samples.partition_point(|(t, _)| *t <= time)
+    }
+}
+
+impl<T: Clone + 'static> AnyChannel for Channel<T> {
+    fn earliest_time(&self) -> Option<f64> {
+        self.samples.front().map(|(t, _)| *t)
+    }
+
+    fn latest_time(&self) -> Option<f64> {
+        self.samples.back().map(|(t, _)| *t)
+    }
+
+    fn sample(&self, time: f64, tolerance: Option<f64>) -> Option<Box<dyn Any>> {
+        let within = |gap: f64| tolerance.is_none_or(|tol| gap <= tol);
+        let i = self.after(time);
+        let before = i.checked_sub(1).and_then(|j| self.samples.get(j));
+        let after = self.samples.get(i);
+        let value = match self.alignment {
+            Alignment::LatestBefore => before
+                .filter(|(t, _)| within(time - t))
+                .map(|(_, v)| v.clone()),
+            Alignment::Nearest => {
+                let nearest = match (before, after) {
+                    (Some(b), Some(a)) if a.0 - time < time - b.0 => Some(a),
+                    (Some(b), _) => Some(b),
+                    (None, a) => a,
+                };
+                nearest
+                    .filter(|(t, _)| within((t - time).abs()))
+                    .map(|(_, v)| v.clone())
+            }
+            Alignment::Interpolated => match (before, after) {
+                (Some((tb, vb)), _) if *tb == time => Some(vb.clone()),
+                (Some((tb, vb)), Some((ta, va))) if within(ta - tb) => {
+                    let interpolate = self.interpolate?;
+                    Some(interpolate(vb, va, (time - tb) / (ta - tb)))
+                }
+                _ => None,
+            },
+        };
+        value.map(|v| Box::new(v) as Box<dyn Any>)
+    }
+
+    fn prune(&mut self, time: f64) {
+        // Keep the last sample at or before `time`, which later bundles may still need.
+        let keep_from = self.after(time).saturating_sub(1);
+        self.samples.drain(..keep_from);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// The number of readings a [`SensorSync`] channel buffers by default.
+pub const DEFAULT_MAX_BUFFERED: usize = 4096;
+
+/// One value per channel of a [`SensorSync`], aligned on a common simulation time.
+#[derive(Debug)]
+pub struct SampleBundle {
+    /// Simulation time of the bundle in seconds.
+    pub time: f64,
+    samples: HashMap<String, Box<dyn Any>>,
+}
+
+impl SampleBundle {
+    /// The value of the named channel, if it exists and holds values of type `T`.
+    pub fn get<T: 'static>(&self, name: &str) -> Option<&T> {
+        self.samples.get(name)?.downcast_ref()
+    }
+
+    /// Remove and return the value of the named channel.
+    pub fn take<T: 'static>(&mut self, name: &str) -> Option<T> {
+        let value = self.samples.remove(name)?;
+        match value.downcast() {
+            Ok(value) => Some(*value),
+            Err(value) => {
+                self.samples.insert(name.to_string(), value);
+                None
+            }
+        }
+    }
+}
+
+/// Buffers readings from several sensors and emits time-aligned [`SampleBundle`]s.
+///
+/// Readings of each channel must be pushed roughly in time order: a bundle at time
+/// `t` is emitted once every channel has a reading at or after `t`, and readings
+/// older than an emitted bundle are discarded except for the last one before it.
+/// Each channel buffers at most [`DEFAULT_MAX_BUFFERED`] readings, or the count set
+/// with [`with_max_buffered`](Self::with_max_buffered), dropping the oldest beyond it,
+/// so a channel that stops receiving readings does not make the others grow forever.
+///
+/// Bundles are emitted either on a fixed grid with [`ready_bundles`](Self::ready_bundles)
+/// or at chosen times, such as those of camera frames, with [`bundle_at`](Self::bundle_at).
+///
+/// # Example
+/// ```no_run
+/// # async fn example(bng: &mut beamng_rs::BeamNg, imu: &beamng_rs::sensors::AdvancedImu, gps: &beamng_rs::sensors::Gps) -> beamng_proto::Result<()> {
+/// use beamng_rs::sensors::{GpsReading, ImuReading, SensorSync};
+///
+/// let mut sync = SensorSync::new(0.05);
+/// sync.add_interpolated::<ImuReading>("imu");
+/// sync.add_latest_before::<GpsReading>("gps");
+///
+/// sync.extend("imu", imu.poll(bng).await?)?;
+/// sync.extend("gps", gps.poll(bng).await?)?;
+/// for bundle in sync.ready_bundles() {
+///     let imu: &ImuReading = bundle.get("imu").unwrap();
+///     let gps: &GpsReading = bundle.get("gps").unwrap();
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct SensorSync {
+    channels: Vec<(String, Box<dyn AnyChannel>)>,
+    period: f64,
+    tolerance: Option<f64>,
+    max_buffered: usize,
+    grid_start: Option<f64>,
+    next_index: u64,
+}
+
+impl SensorSync {
+    /// Create a synchronizer emitting bundles every `period` seconds of simulation time.
+    pub fn new(period: f64) -> Self {
+        Self {
+            channels: Vec::new(),
+            period,
+            tolerance: None,
+            max_buffered: DEFAULT_MAX_BUFFERED,
+            grid_start: None,
+            next_index: 0,
+        }
+    }
+
+    /// Skip bundles for which a channel has no reading within `tolerance` seconds:
+    /// of the bundle time for nearest and latest-before channels, and between the
+    /// two readings around it for interpolated channels.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    /// Buffer at most `max_buffered` readings per channel, dropping the oldest.
+    pub fn with_max_buffered(mut self, max_buffered: usize) -> Self {
+        self.max_buffered = max_buffered;
+        self
+    }
+
+    fn add_channel<T: Clone + 'static>(&mut self, name: &str, channel: Channel<T>) {
+        self.channels.retain(|(n, _)| n != name);
+        self.channels.push((name.to_string(), Box::new(channel)));
+    }
+
+    /// Add a channel sampled with the reading nearest to the bundle time.
+    pub fn add_nearest<T: Clone + 'static>(&mut self, name: &str) {
+        self.add_channel(name, Channel::<T>::new(Alignment::Nearest, None));
+    }
+
+    /// Add a channel sampled with the latest reading at or before the bundle time.
+    pub fn add_latest_before<T: Clone + 'static>(&mut self, name: &str) {
+        self.add_channel(name, Channel::<T>::new(Alignment::LatestBefore, None));
+    }
+
+    /// Add a channel sampled by interpolating the readings around the bundle time.
+    pub fn add_interpolated<T: Interpolate + Clone + 'static>(&mut self, name: &str) {
+        self.add_channel(
+            name,
+            Channel::<T>::new(Alignment::Interpolated, Some(T::interpolate)),
+        );
+    }
+
+    fn channel_mut<T: 'static>(&mut self, name: &str) -> Result<&mut Channel<T>> {
+        let (_, channel) = self
+            .channels
+            .iter_mut()
+            .find(|(n, _)| n == name)
+            .ok_or_else(|| BngError::ValueError(format!("Unknown sync channel \"{name}\"")))?;
+        channel.as_any_mut().downcast_mut().ok_or_else(|| {
+            BngError::ValueError(format!(
+                "Sync channel \"{name}\" does not hold {}",
+                std::any::type_name::<T>()
+            ))
+        })
+    }
+
+    /// Buffer a reading taken at `time`, for sensors whose readings carry no timestamp.
+    pub fn push<T: 'static>(&mut self, name: &str, time: f64, value: T) -> Result<()> {
+        let capacity = self.max_buffered;
+        self.channel_mut(name)?.push(time, value, capacity);
+        Ok(())
+    }
+
+    /// Buffer a batch of timestamped readings, such as the result of a sensor poll.
+    pub fn extend<T: Timestamped + 'static>(&mut self, name: &str, values: Vec<T>) -> Result<()> {
+        let capacity = self.max_buffered;
+        let channel = self.channel_mut(name)?;
+        for value in values {
+            channel.push(value.time(), value, capacity);
+        }
+        Ok(())
+    }
+
+    /// Whether every channel has a reading at or after `time`.
+    fn is_ready(&self, time: f64) -> bool {
+        !self.channels.is_empty()
+            && self
+                .channels
+                .iter()
+                .all(|(_, c)| c.latest_time().is_some_and(|t| t >= time))
+    }
+
+    fn sample(&self, time: f64) -> Option<SampleBundle> {
+        let samples = self
+            .channels
+            .iter()
+            .map(|(name, c)| Some((name.clone(), c.sample(time, self.tolerance)?)))
+            .collect::<Option<_>>()?;
+        Some(SampleBundle { time, samples })
+    }
+
+    fn prune(&mut self, time: f64) {
+        for (_, channel) in &mut self.channels {
+            channel.prune(time);
+        }
+    }
+
+    /// The bundle at `time`, or `None` if a channel has no reading at or after it yet
+    /// or no reading within the tolerance.
+    ///
+    /// Readings no longer needed for later times are discarded, so call this with
+    /// increasing times.
+    pub fn bundle_at(&mut self, time: f64) -> Option<SampleBundle> {
+        if !self.is_ready(time) {
+            return None;
+        }
+        let bundle = self.sample(time);
+        self.prune(time);
+        bundle
+    }
+
+    /// Emit every bundle on the `period` grid whose time all channels have reached.
+    ///
+    /// The grid starts at the first multiple of the period at which every channel
+    /// has a reading. Grid times skipped for lack of a reading within the tolerance
+    /// produce no bundle.
+    pub fn ready_bundles(&mut self) -> Vec<SampleBundle> {
+        let mut bundles = Vec::new();
+        if self.period <= 0.0 {
+            return bundles;
+        }
+        if self.grid_start.is_none() {
+            let earliest = self
+                .channels
+                .iter()
+                .map(|(_, c)| c.earliest_time())
+                .collect::<Option<Vec<_>>>()
+                .and_then(|ts| ts.into_iter().reduce(f64::max));
+            let Some(earliest) = earliest else {
+                return bundles;
+            };
+            self.grid_start = Some((earliest / self.period).ceil() * self.period);
+        }
+        let start = self.grid_start.unwrap_or_default();
+        loop {
+            let time = start + self.next_index as f64 * self.period;
+            if !self.is_ready(time) {
+                break;
+            }
+            bundles.extend(self.sample(time));
+            self.prune(time);
+            self.next_index += 1;
+        }
+        bundles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alignment_modes() {
+        let mut sync = SensorSync::new(0.5).with_tolerance(0.35);
+        sync.add_interpolated::<f64>("speed");
+        sync.add_nearest::<&str>("frame");
+        sync.add_latest_before::<GpsReading>("gps");
+
+        for i in 0..=10 {
+            let t = i as f64 * 0.1;
+            sync.push("speed", t, t * 10.0).unwrap();
+        }
+        sync.push("frame", 0.05, "a").unwrap();
+        sync.push("frame", 0.42, "b").unwrap();
+        sync.push("frame", 1.1, "c").unwrap();
+        let gps = |time| GpsReading {
+            time,
+            ..Default::default()
+        };
+        sync.extend("gps", vec![gps(0.2), gps(0.9), gps(1.2)])
+            .unwrap();
+        assert!(sync.push("speed", 0.0, 1u32).is_err());
+
+        // Grid starts at 0.5, the first multiple of the period all channels reach.
+        let bundles = sync.ready_bundles();
+        let times: Vec<f64> = bundles.iter().map(|b| b.time).collect();
+        assert_eq!(times, vec![0.5, 1.0]);
+        let b = &bundles[0];
+        assert!((b.get::<f64>("speed").unwrap() - 5.0).abs() < 1e-9);
+        assert_eq!(b.get::<&str>("frame"), Some(&"b"));
+        assert_eq!(b.get::<GpsReading>("gps").unwrap().time, 0.2);
+        assert_eq!(bundles[1].get::<&str>("frame"), Some(&"c"));
+        assert_eq!(bundles[1].get::<GpsReading>("gps").unwrap().time, 0.9);
+
+        // Speed has no reading past 1.0 yet.
+        assert!(sync.bundle_at(1.1).is_none());
+    }
+
+    #[test]
+    fn test_buffers_are_bounded() {
+        let mut sync = SensorSync::new(0.1).with_max_buffered(3);
+        sync.add_nearest::<f64>("fast");
+        sync.add_nearest::<f64>("stalled");
+        for i in 0..10 {
+            sync.push("fast", i as f64, i as f64).unwrap();
+        }
+        // Nothing is emitted while "stalled" has no readings, but only the three
+        // newest "fast" readings are kept.
+        assert!(sync.ready_bundles().is_empty());
+        sync.push("stalled", 9.0, 0.0).unwrap();
+        let bundle = sync.bundle_at(6.0).unwrap();
+        assert_eq!(bundle.get::<f64>("fast"), Some(&7.0));
+    }
+}
@@ -0,0 +1,20 @@
+use std::collections::HashMap;
+
+use beamng_proto::types::StrDict;
+
+use super::sensor::Sensor;
+
+/// The timer sensor reports the simulation time in seconds since the scenario started.
+pub struct Timer;
+
+impl Sensor for Timer {
+    fn encode_vehicle_request(&self) -> StrDict {
+        let mut req = HashMap::new();
+        req.insert("type".to_string(), rmpv::Value::from("Timer"));
+        req
+    }
+
+    fn decode_response(&self, resp: &StrDict) -> Option<rmpv::Value> {
+        resp.get("time").cloned()
+    }
+}
@@ -0,0 +1,460 @@
+use beamng_proto::types::{Float2, Int2, StrDict, Vec3};
+use beamng_proto::{BngError, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::beamng::BeamNg;
+use crate::sensors::registry::{mark_dropped, SharedSensorRegistry};
+use crate::sensors::{GeSensor, SensorKind, State};
+use crate::vehicle::Vehicle;
+
+/// Configuration for an [`Ultrasonic`] sensor.
+///
+/// All fields have defaults matching the Python SDK.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UltrasonicConfig {
+    pub requested_update_time: f64,
+    pub update_priority: f64,
+    pub pos: Vec3,
+    pub dir: Vec3,
+    pub up: Vec3,
+    pub resolution: Int2,
+    pub field_of_view_y: f64,
+    pub near_far_planes: Float2,
+    pub range_roundness: f64,
+    pub range_cutoff_sensitivity: f64,
+    pub range_shape: f64,
+    pub range_focus: f64,
+    pub range_min_cutoff: f64,
+    pub range_direct_max_cutoff: f64,
+    pub sensitivity: f64,
+    pub fixed_window_size: f64,
+    pub is_visualised: bool,
+    pub is_streaming: bool,
+    pub is_static: bool,
+    pub is_snapping_desired: bool,
+    pub is_force_inside_triangle: bool,
+    pub is_dir_world_space: bool,
+}
+
+impl Default for UltrasonicConfig {
+    fn default() -> Self {
+        Self {
+            requested_update_time: 0.1,
+            update_priority: 0.0,
+            pos: (0.0, 0.0, 1.7),
+            dir: (0.0, -1.0, 0.0),
+            up: (0.0, 0.0, 1.0),
+            resolution: (200, 200),
+            field_of_view_y: 5.7,
+            near_far_planes: (0.1, 5.1),
+            range_roundness: -1.15,
+            range_cutoff_sensitivity: 0.0,
+            range_shape: 0.3,
+            range_focus: 0.376,
+            range_min_cutoff: 0.1,
+            range_direct_max_cutoff: 5.0,
+            sensitivity: 3.0,
+            fixed_window_size: 10.0,
+            is_visualised: true,
+            is_streaming: false,
+            is_static: false,
+            is_snapping_desired: false,
+            is_force_inside_triangle: false,
+            is_dir_world_space: false,
+        }
+    }
+}
+
+/// A single ultrasonic distance reading.
+#[derive(Debug, Clone, Default)]
+pub struct UltrasonicReading {
+    /// Measured distance to the nearest obstacle in metres.
+    pub distance: f64,
+    /// Lower bound of the sliding measurement window.
+    pub window_min: f64,
+    /// Upper bound of the sliding measurement window.
+    pub window_max: f64,
+}
+
+fn parse_reading(map: &StrDict) -> UltrasonicReading {
+    UltrasonicReading {
+        distance: map.get("distance").and_then(|v| v.as_f64()).unwrap_or(0.0),
+        window_min: map.get("windowMin").and_then(|v| v.as_f64()).unwrap_or(0.0),
+        window_max: map.get("windowMax").and_then(|v| v.as_f64()).unwrap_or(0.0),
+    }
+}
+
+fn extract_vec3(val: &rmpv::Value) -> Option<Vec3> {
+    let arr = val.as_array()?;
+    if arr.len() < 3 {
+        return None;
+    }
+    Some((arr[0].as_f64()?, arr[1].as_f64()?, arr[2].as_f64()?))
+}
+
+fn distance(a: Vec3, b: Vec3) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2) + (a.2 - b.2).powi(2)).sqrt()
+}
+
+/// The eight world-space corners of a `GetBBoxPoints` response.
+///
+/// The simulator orders them as front-bottom-right, front-top-right, front-top-left,
+/// front-bottom-left, followed by the same four corners at the rear.
+fn bbox_points(resp: &StrDict) -> Option<Vec<Vec3>> {
+    let points: Vec<Vec3> = resp
+        .get("points")?
+        .as_array()?
+        .iter()
+        .map(extract_vec3)
+        .collect::<Option<_>>()?;
+    (points.len() >= 8).then_some(points)
+}
+
+/// Compute the vehicle's (width, length, height) from its bounding box corners.
+fn bbox_extents(points: &[Vec3]) -> Vec3 {
+    let width = distance(points[0], points[3]);
+    let length = distance(points[0], points[4]);
+    let height = distance(points[0], points[1]);
+    (width, length, height)
+}
+
+/// Express the centre of the bounding box in the vehicle frame (`+x` left, `+y` rear,
+/// `+z` up) with its origin at `ref_pos`, the world position of the reference node.
+fn bbox_centre_offset(points: &[Vec3], ref_pos: Vec3) -> Vec3 {
+    let sub = |a: Vec3, b: Vec3| (a.0 - b.0, a.1 - b.1, a.2 - b.2);
+    let dot = |a: Vec3, b: Vec3| a.0 * b.0 + a.1 * b.1 + a.2 * b.2;
+    let unit = |v: Vec3| {
+        let n = dot(v, v).sqrt();
+        if n > f64::EPSILON {
+            (v.0 / n, v.1 / n, v.2 / n)
+        } else {
+            (0.0, 0.0, 0.0)
+        }
+    };
+    let n = points.len() as f64;
+    let centre = points.iter().fold((0.0, 0.0, 0.0), |c, p| {
+        (c.0 + p.0 / n, c.1 + p.1 / n, c.2 + p.2 / n)
+    });
+    let left = unit(sub(points[3], points[0]));
+    let rear = unit(sub(points[4], points[0]));
+    let up = unit(sub(points[1], points[0]));
+    let offset = sub(centre, ref_pos);
+    (dot(offset, left), dot(offset, rear), dot(offset, up))
+}
+
+/// Compute `(pos, dir)` pairs for `count` sensors evenly spaced around a rectangular
+/// footprint of the given `width` and `length`, relative to the footprint's centre.
+///
+/// The first sensor faces straight ahead (`-y`), and each sensor is placed where its
+/// outward ray from the centre meets the footprint edge, at the given `height`.
+pub fn ring_poses(width: f64, length: f64, height: f64, count: usize) -> Vec<(Vec3, Vec3)> {
+    let half_w = width / 2.0;
+    let half_l = length / 2.0;
+    (0..count)
+        .map(|i| {
+            let theta = std::f64::consts::TAU * i as f64 / count as f64;
+            let (dx, dy) = (theta.sin(), -theta.cos());
+            let tx = if dx.abs() > f64::EPSILON {
+                half_w / dx.abs()
+            } else {
+                f64::INFINITY
+            };
+            let ty = if dy.abs() > f64::EPSILON {
+                half_l / dy.abs()
+            } else {
+                f64::INFINITY
+            };
+            let t = tx.min(ty);
+            ((dx * t, dy * t, height), (dx, dy, 0.0))
+        })
+        .collect()
+}
+
+/// An ultrasonic parking sensor attached to the simulator (GE-level), optionally tracking a vehicle.
+pub struct Ultrasonic {
+    name: String,
+    vid: Option<String>,
+    registry: SharedSensorRegistry,
+}
+
+impl Drop for Ultrasonic {
+    fn drop(&mut self) {
+        mark_dropped(&self.registry, &self.name);
+    }
+}
+
+impl Ultrasonic {
+    /// Open an ultrasonic sensor in the simulator.
+    pub async fn open(
+        name: impl Into<String>,
+        bng: &mut BeamNg,
+        vehicle: Option<&Vehicle>,
+        config: UltrasonicConfig,
+    ) -> Result<Self> {
+        let name = name.into();
+        bng.check_sensor_name(&name)?;
+        let vid = vehicle.map(|v| v.vid.clone());
+
+        let vid_val: rmpv::Value = match &vid {
+            Some(v) => rmpv::Value::from(v.as_str()),
+            None => rmpv::Value::from(0),
+        };
+
+        let fields: Vec<(&str, rmpv::Value)> = vec![
+            ("name", rmpv::Value::from(name.as_str())),
+            ("vid", vid_val),
+            (
+                "updateTime",
+                rmpv::Value::from(config.requested_update_time),
+            ),
+            ("priority", rmpv::Value::from(config.update_priority)),
+            (
+                "pos",
+                rmpv::Value::Array(vec![
+                    rmpv::Value::from(config.pos.0),
+                    rmpv::Value::from(config.pos.1),
+                    rmpv::Value::from(config.pos.2),
+                ]),
+            ),
+            (
+                "dir",
+                rmpv::Value::Array(vec![
+                    rmpv::Value::from(config.dir.0),
+                    rmpv::Value::from(config.dir.1),
+                    rmpv::Value::from(config.dir.2),
+                ]),
+            ),
+            (
+                "up",
+                rmpv::Value::Array(vec![
+                    rmpv::Value::from(config.up.0),
+                    rmpv::Value::from(config.up.1),
+                    rmpv::Value::from(config.up.2),
+                ]),
+            ),
+            (
+                "size",
+                rmpv::Value::Array(vec![
+                    rmpv::Value::from(config.resolution.0),
+                    rmpv::Value::from(config.resolution.1),
+                ]),
+            ),
+            ("fovY", rmpv::Value::from(config.field_of_view_y)),
+            (
+                "near_far_planes",
+                rmpv::Value::Array(vec![
+                    rmpv::Value::from(config.near_far_planes.0),
+                    rmpv::Value::from(config.near_far_planes.1),
+                ]),
+            ),
+            ("range_roundness", rmpv::Value::from(config.range_roundness)),
+            (
+                "range_cutoff_sensitivity",
+                rmpv::Value::from(config.range_cutoff_sensitivity),
+            ),
+            ("range_shape", rmpv::Value::from(config.range_shape)),
+            ("range_focus", rmpv::Value::from(config.range_focus)),
+            (
+                "range_min_cutoff",
+                rmpv::Value::from(config.range_min_cutoff),
+            ),
+            (
+                "range_direct_max_cutoff",
+                rmpv::Value::from(config.range_direct_max_cutoff),
+            ),
+            ("sensitivity", rmpv::Value::from(config.sensitivity)),
+            (
+                "fixed_window_size",
+                rmpv::Value::from(config.fixed_window_size),
+            ),
+            ("isVisualised", rmpv::Value::from(config.is_visualised)),
+            ("isStreaming", rmpv::Value::from(config.is_streaming)),
+            ("isStatic", rmpv::Value::from(config.is_static)),
+            (
+                "isSnappingDesired",
+                rmpv::Value::from(config.is_snapping_desired),
+            ),
+            (
+                "isForceInsideTriangle",
+                rmpv::Value::from(config.is_force_inside_triangle),
+            ),
+            (
+                "isDirWorldSpace",
+                rmpv::Value::from(config.is_dir_world_space),
+            ),
+        ];
+
+        bng.conn()?
+            .ack("OpenUltrasonic", "OpenedUltrasonic", &fields)
+            .await?;
+
+        info!("Opened Ultrasonic: \"{}\"", name);
+        bng.register_sensor(SensorKind::Ultrasonic, &name, vid.as_deref());
+
+        Ok(Self {
+            name,
+            vid,
+            registry: bng.shared_sensor_registry(),
+        })
+    }
+
+    /// Open a ring of `count` ultrasonic sensors around a vehicle.
+    ///
+    /// The vehicle's footprint is taken from [`RootApi::get_bbox`](crate::api::vehicle::RootApi::get_bbox)
+    /// and sensors are named `{prefix}_0`, `{prefix}_1`, ... starting at the front.
+    /// Positions are given relative to the vehicle's reference node, like any sensor
+    /// `pos`, with `height` measured from it.
+    /// The `pos` and `dir` of `config` are overridden per sensor; all other fields are shared.
+    ///
+    /// If any sensor fails to open, those already opened are closed again.
+    pub async fn open_ring(
+        prefix: &str,
+        bng: &mut BeamNg,
+        vehicle: &mut Vehicle,
+        count: usize,
+        height: f64,
+        config: UltrasonicConfig,
+    ) -> Result<Vec<Self>> {
+        let bbox = vehicle.root().get_bbox().await?;
+        let points = bbox_points(&bbox)
+            .ok_or_else(|| BngError::ValueError("Missing points in GetBBoxPoints".into()))?;
+        let ref_pos = vehicle
+            .poll_sensor("state", &State)
+            .await?
+            .as_ref()
+            .and_then(|state| state.as_map())
+            .and_then(|m| m.iter().find(|(k, _)| k.as_str() == Some("pos")))
+            .and_then(|(_, pos)| extract_vec3(pos))
+            .ok_or_else(|| BngError::ValueError("Missing pos in vehicle state".into()))?;
+        let (width, length, _) = bbox_extents(&points);
+        let (cx, cy, _) = bbox_centre_offset(&points, ref_pos);
+
+        let mut sensors = Vec::with_capacity(count);
+        for (i, (pos, dir)) in ring_poses(width, length, height, count)
+            .into_iter()
+            .enumerate()
+        {
+            let sensor_config = UltrasonicConfig {
+                pos: (pos.0 + cx, pos.1 + cy, pos.2),
+                dir,
+                is_dir_world_space: false,
+                ..config.clone()
+            };
+            match Self::open(format!("{prefix}_{i}"), bng, Some(&*vehicle), sensor_config).await {
+                Ok(sensor) => sensors.push(sensor),
+                Err(e) => {
+                    for sensor in sensors {
+                        let name = sensor.name.clone();
+                        if let Err(close_err) = sensor.close(bng).await {
+                            warn!("Failed to close Ultrasonic \"{name}\": {close_err}");
+                        }
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(sensors)
+    }
+
+    /// Poll the sensor for its latest distance reading.
+    pub async fn poll(&self, bng: &mut BeamNg) -> Result<UltrasonicReading> {
+        GeSensor::poll(self, bng).await
+    }
+
+    /// Close the sensor.
+    pub async fn close(self, bng: &mut BeamNg) -> Result<()> {
+        bng.close_sensor(&self.name).await
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the ID of the vehicle this sensor is attached to, if any.
+    pub fn vid(&self) -> Option<&str> {
+        self.vid.as_deref()
+    }
+}
+
+impl GeSensor for Ultrasonic {
+    type Config = UltrasonicConfig;
+    type Reading = UltrasonicReading;
+
+    async fn open_sensor(
+        name: impl Into<String> + Send,
+        bng: &mut BeamNg,
+        vehicle: Option<&Vehicle>,
+        config: UltrasonicConfig,
+    ) -> Result<Self> {
+        Ultrasonic::open(name, bng, vehicle, config).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn poll_request(&self) -> (&'static str, Vec<(&str, rmpv::Value)>) {
+        (
+            "PollUltrasonic",
+            vec![("name", rmpv::Value::from(self.name.as_str()))],
+        )
+    }
+
+    fn decode_poll(&self, resp: &StrDict) -> UltrasonicReading {
+        resp.get("data")
+            .cloned()
+            .and_then(beamng_proto::types::value_to_str_dict)
+            .map(|m| parse_reading(&m))
+            .unwrap_or_default()
+    }
+
+    async fn close(self, bng: &mut BeamNg) -> Result<()> {
+        Ultrasonic::close(self, bng).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bbox_and_ring_poses() {
+        // A 2 x 4 x 1.5 box facing world -y, with the reference node 1 m ahead of its
+        // centre and 0.5 m to the left.
+        let corners = [
+            (-1.0, -2.0, 0.0),
+            (-1.0, -2.0, 1.5),
+            (1.0, -2.0, 1.5),
+            (1.0, -2.0, 0.0),
+            (-1.0, 2.0, 0.0),
+            (-1.0, 2.0, 1.5),
+            (1.0, 2.0, 1.5),
+            (1.0, 2.0, 0.0),
+        ];
+        let points: Vec<rmpv::Value> = corners
+            .iter()
+            .map(|c| rmpv::Value::Array(vec![c.0.into(), c.1.into(), c.2.into()]))
+            .collect();
+        let resp = StrDict::from([("points".to_string(), rmpv::Value::Array(points))]);
+        let points = bbox_points(&resp).unwrap();
+        assert_eq!(bbox_extents(&points), (2.0, 4.0, 1.5));
+        let (cx, cy, cz) = bbox_centre_offset(&points, (0.5, -1.0, 0.0));
+        assert!((cx + 0.5).abs() < 1e-9 && (cy - 1.0).abs() < 1e-9 && (cz - 0.75).abs() < 1e-9);
+
+        let poses = ring_poses(2.0, 4.0, 0.5, 4);
+        let expected = [
+            ((0.0, -2.0), (0.0, -1.0)),
+            ((1.0, 0.0), (1.0, 0.0)),
+            ((0.0, 2.0), (0.0, 1.0)),
+            ((-1.0, 0.0), (-1.0, 0.0)),
+        ];
+        for ((pos, dir), (p, d)) in poses.iter().zip(expected) {
+            assert!((pos.0 - p.0).abs() < 1e-9 && (pos.1 - p.1).abs() < 1e-9);
+            assert!((dir.0 - d.0).abs() < 1e-9 && (dir.1 - d.1).abs() < 1e-9);
+            assert_eq!(pos.2, 0.5);
+        }
+        assert!(bbox_points(&StrDict::new()).is_none());
+    }
+}
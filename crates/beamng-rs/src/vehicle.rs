@@ -1,7 +1,10 @@
 use beamng_proto::types::{Color, StrDict};
 use beamng_proto::Connection;
+use serde::de::DeserializeOwned;
 
-use crate::api::vehicle::{AIApi, RootApi};
+use crate::api::vehicle::{AIApi, ControlsApi, RootApi};
+use crate::lua::decode_result;
+use crate::sensors::Sensor;
 
 /// A vehicle in the BeamNG.tech simulation.
 pub struct Vehicle {
@@ -111,6 +114,87 @@ impl Vehicle {
         conn.request(req_type, fields).await
     }
 
+    /// Execute a Lua chunk in the vehicle's own Lua VM.
+    ///
+    /// If `response` is true, the value the chunk returns is sent back.
+    pub async fn queue_lua_command(
+        &mut self,
+        chunk: &str,
+        response: bool,
+    ) -> beamng_proto::Result<Option<rmpv::Value>> {
+        let resp = self
+            .send_vehicle_request(
+                "QueueLuaCommand",
+                &[
+                    ("chunk", rmpv::Value::from(chunk)),
+                    ("resp", rmpv::Value::from(response)),
+                ],
+            )
+            .await?;
+        Ok(resp.get("resp").cloned())
+    }
+
+    /// Execute a Lua chunk in the vehicle's Lua VM and decode the value it returns.
+    ///
+    /// A chunk returning nothing decodes as `()` or `None`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn example(ego: &mut beamng_rs::vehicle::Vehicle) -> beamng_proto::Result<()> {
+    /// #[derive(serde::Deserialize)]
+    /// struct Wheel {
+    ///     name: String,
+    ///     radius: f64,
+    /// }
+    ///
+    /// let wheels: Vec<Wheel> = ego
+    ///     .queue_lua_command_as(
+    ///         "local t = {} for _, w in pairs(wheels.wheels) do \
+    ///          table.insert(t, {name = w.name, radius = w.radius}) end return t",
+    ///     )
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn queue_lua_command_as<T: DeserializeOwned>(
+        &mut self,
+        chunk: &str,
+    ) -> beamng_proto::Result<T> {
+        decode_result(self.queue_lua_command(chunk, true).await?)
+    }
+
+    /// Poll a single vehicle sensor (e.g. [`State`](crate::sensors::State)) over the
+    /// per-vehicle connection, returning its decoded value.
+    pub async fn poll_sensor(
+        &mut self,
+        name: &str,
+        sensor: &dyn Sensor,
+    ) -> beamng_proto::Result<Option<rmpv::Value>> {
+        let request = rmpv::Value::Map(
+            sensor
+                .encode_vehicle_request()
+                .into_iter()
+                .map(|(k, v)| (rmpv::Value::from(k), v))
+                .collect(),
+        );
+        let resp = self
+            .send_vehicle_request(
+                "SensorRequest",
+                &[(
+                    "sensors",
+                    rmpv::Value::Map(vec![(rmpv::Value::from(name), request)]),
+                )],
+            )
+            .await?;
+        let data = resp
+            .get("data")
+            .cloned()
+            .and_then(beamng_proto::types::value_to_str_dict)
+            .and_then(|d| d.get(name).cloned())
+            .and_then(beamng_proto::types::value_to_str_dict);
+        Ok(data.and_then(|d| sensor.decode_response(&d)))
+    }
+
     /// Access the AI control API for this vehicle.
     pub fn ai(&mut self) -> AIApi<'_> {
         AIApi { vehicle: self }
@@ -121,6 +205,11 @@ impl Vehicle {
         RootApi { vehicle: self }
     }
 
+    /// Access the vehicle controls API (lights, shift mode, horn, paint, recovery, parts).
+    pub fn controls(&mut self) -> ControlsApi<'_> {
+        ControlsApi { vehicle: self }
+    }
+
     /// Disconnect the per-vehicle connection.
     pub fn disconnect(&mut self) {
         self.connection = None;